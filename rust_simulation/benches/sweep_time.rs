@@ -0,0 +1,66 @@
+// Time of one Swendsen-Wang sweep (labelling + flip) against the lattice size L,
+// below, at and above the critical temperature, for every union-find & labelling strategy.
+//      cargo bench --bench sweep_time
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use swendsen_wang::swendsen_wang_algorithm::{IsingArray2D, LabellingStrategy, SwendsenWangAlgorithm, UnionFindStrategy, J};
+
+const TEMPERATURES: [f64; 3] = [2.0, 2.269, 3.0];
+const LENGTHS: [usize; 6]    = [32, 64, 128, 256, 512, 1024];
+const THERM_STEPS: usize     = 200;
+const LARGE_LENGTH: usize    = 512; // and above: criterion's minimum number of samples (default 100)
+const LARGE_SAMPLES: usize   = 10;
+const STRATEGIES: [(LabellingStrategy, UnionFindStrategy); 5] = [
+    (LabellingStrategy::HoshenKopelman,   UnionFindStrategy::SmallestLabel),
+    (LabellingStrategy::HoshenKopelman,   UnionFindStrategy::BySize),
+    (LabellingStrategy::UnionFind,        UnionFindStrategy::BySize),
+    (LabellingStrategy::FloodFill,        UnionFindStrategy::SmallestLabel),
+    (LabellingStrategy::LabelEquivalence, UnionFindStrategy::SmallestLabel),
+];
+
+fn sweep(swendsen_wang: &mut SwendsenWangAlgorithm, spins: &mut IsingArray2D, rng: &mut SmallRng, proba_add: f64)
+{
+    swendsen_wang.perform_swendsen_wang_all(spins, rng, proba_add);
+    swendsen_wang.flip_cluster_and_take_fourier(spins, rng);
+    swendsen_wang.reset();
+}
+
+fn bench_sweep_time(c: &mut Criterion)
+{
+    for temp in TEMPERATURES
+    {
+        let proba_add = 1f64 - (-2_f64*J/temp).exp();
+        let mut group = c.benchmark_group(format!("sweep_T={temp}"));
+
+        for length in LENGTHS
+        {
+            group.throughput(Throughput::Elements((length*length) as u64));
+            group.sample_size(if length >= LARGE_LENGTH { LARGE_SAMPLES } else { 100 });
+            for (labelling, union_find) in STRATEGIES
+            {
+                let name = match labelling
+                {
+                    LabellingStrategy::HoshenKopelman | LabellingStrategy::UnionFind => format!("{}_{}", labelling.name(), union_find.name()),
+                    _ => labelling.name().to_string(),
+                };
+                let mut rng           = SmallRng::seed_from_u64(1234);
+                let mut spins         = IsingArray2D::new_randomized(&mut rng, length, length);
+                let mut swendsen_wang = SwendsenWangAlgorithm::with_labelling(length, length, labelling, union_find);
+                for _ in 0..THERM_STEPS
+                {
+                    sweep(&mut swendsen_wang, &mut spins, &mut rng, proba_add);
+                }
+                group.bench_with_input(BenchmarkId::new(name, length), &length, |b, _|
+                {
+                    b.iter(|| sweep(&mut swendsen_wang, &mut spins, &mut rng, proba_add))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_sweep_time);
+criterion_main!(benches);
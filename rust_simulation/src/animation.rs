@@ -0,0 +1,146 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use rand::{rngs::SmallRng, SeedableRng};
+
+use crate::configuration::InitialState;
+use crate::random::derive_seed;
+use crate::snapshot::{cluster_colour, Image};
+use crate::swendsen_wang_algorithm::{ClusterMap, IsingArray2D, SwendsenWangAlgorithm, J};
+
+
+// Animated GIF of the Swendsen-Wang dynamics at one temperature, for teaching & talks:
+//      let animation = Animation { rows: 64, cols: 64, temperature: 2.269, steps: 50, show_clusters: true, ..Default::default() };
+//      animation.write_to_file("sw.gif")?;
+// After therm_steps (unrecorded) updates, the frames are the configuration, then for every one of the `steps` updates
+// (with show_clusters) the Fortuin-Kasteleyn clusters built on the configuration, each in its own colour, & the configuration
+// once the clusters are flipped. The frames are drawn with Animation::palette: no colour quantisation is needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation
+{
+    pub rows: usize,
+    pub cols: usize,
+    pub temperature: f64,
+    pub therm_steps: usize,
+    pub steps: usize,
+    pub show_clusters: bool,
+    pub scale: usize,        // pixels per spin (side)
+    pub frame_delay: u16,    // hundredths of a second
+    pub seed: u64,
+    pub initial_state: InitialState,
+}
+
+impl Default for Animation
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            rows: 32,
+            cols: 32,
+            temperature: 2.269,
+            therm_steps: 0,
+            steps: 20,
+            show_clusters: false,
+            scale: 4,
+            frame_delay: 50,
+            seed: 0,
+            initial_state: InitialState::default(),
+        }
+    }
+}
+
+impl Animation
+{
+    pub const UP: u8   = 0; // palette index of the up spins (white)
+    pub const DOWN: u8 = 1; // of the down spins (black), the clusters use the 254 other colours
+
+    // RGB triplets of the 256 colours of every frame
+    pub fn palette() -> Vec<u8>
+    {
+        let spins = [[255, 255, 255], [0, 0, 0]];
+        spins.into_iter().chain((0..254).map(cluster_colour)).flatten().collect()
+    }
+    // 1 + steps frames (1 + 2 steps with show_clusters) of palette indices, in order
+    pub fn record(&self) -> Result<Vec<Image>, String>
+    {
+        let &Animation { rows, cols, temperature, scale, .. } = self;
+        if rows < 2 || cols < 2 || cols * scale.max(1) > u16::MAX as usize || rows * scale.max(1) > u16::MAX as usize
+        {
+            return Err(format!("Cannot animate a {rows}x{cols} lattice at scale {scale} (at least 2x2, at most {} pixels a side)", u16::MAX));
+        }
+        if !(temperature.is_finite() && temperature > 0_f64)
+        {
+            return Err(format!("Temperatures should be positive (got {temperature})"));
+        }
+        let mut rng   = SmallRng::seed_from_u64(derive_seed(self.seed, 0, 0));
+        let mut spins = self.initial_state.configuration(rows, cols)
+            .map_err(|err| format!("Could not load the initial configuration: {err}"))?
+            .unwrap_or_else(|| IsingArray2D::new_randomized(&mut rng, rows, cols));
+        let mut swendsen_wang = SwendsenWangAlgorithm::<usize>::new(rows, cols);
+        let proba_add         = 1f64 - (-2_f64*J/temperature).exp();
+
+        let mut frames = Vec::with_capacity(1 + self.steps * (1 + self.show_clusters as usize));
+        for step in 0..self.therm_steps + self.steps
+        {
+            let is_recorded = step >= self.therm_steps;
+            if is_recorded && frames.is_empty()
+            {
+                frames.push(self.spin_frame(&spins));
+            }
+            swendsen_wang.perform_swendsen_wang_all(&spins, &mut rng, proba_add);
+            if is_recorded && self.show_clusters
+            {
+                frames.push(self.cluster_frame(&swendsen_wang.cluster_map(&spins)));
+            }
+            swendsen_wang.flip_cluster_and_take_fourier(&mut spins, &mut rng);
+            swendsen_wang.reset();
+            if is_recorded
+            {
+                frames.push(self.spin_frame(&spins));
+            }
+        }
+        if frames.is_empty()
+        {
+            frames.push(self.spin_frame(&spins));
+        }
+        Ok(frames)
+    }
+    fn spin_frame(&self, spins: &IsingArray2D) -> Image
+    {
+        let (rows, cols) = spins.shape();
+        Image::from_sites(rows as usize, cols as usize, self.scale, 1, |site| if spins.as_slice()[site] > 0 { &[Self::UP] } else { &[Self::DOWN] })
+    }
+    fn cluster_frame(&self, clusters: &ClusterMap) -> Image
+    {
+        let (rows, cols) = clusters.shape();
+        let indices: Vec<u8> = (0..clusters.num_clusters()).map(|label| 2 + (label % 254) as u8).collect();
+        Image::from_sites(rows, cols, self.scale, 1, |site| std::slice::from_ref(&indices[clusters.labels()[site]]))
+    }
+
+    // looping GIF of `frames` (as recorded), frame_delay apart
+    pub fn write_gif<W: Write>(&self, frames: &[Image], writer: W) -> std::io::Result<()>
+    {
+        let Some(first) = frames.first() else
+        {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "no frames to animate"));
+        };
+        let (width, height) = (first.width() as u16, first.height() as u16);
+        let mut encoder     = gif::Encoder::new(writer, width, height, &Self::palette()).map_err(std::io::Error::other)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(std::io::Error::other)?;
+        for image in frames
+        {
+            let frame = gif::Frame { width, height, delay: self.frame_delay, buffer: Cow::Borrowed(image.pixels()), ..Default::default() };
+            encoder.write_frame(&frame).map_err(std::io::Error::other)?;
+        }
+        encoder.into_inner()?.flush()
+    }
+    // records & writes the animation, returns the number of frames
+    pub fn write_to_file(&self, file_name: &str) -> std::io::Result<usize>
+    {
+        let frames = self.record().map_err(std::io::Error::other)?;
+        self.write_gif(&frames, BufWriter::new(File::create(file_name)?))?;
+        Ok(frames.len())
+    }
+}
@@ -0,0 +1,104 @@
+use crate::swendsen_wang_algorithm::IsingArray2D;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"SWCKPT01";
+
+// Snapshot of a single temperature run.
+// The full state of the random generator is stored (RngState), so resuming from a checkpoint continues the very
+// same stream: the results are those of an uninterrupted run, with or without checkpoints.
+// The first checkpoint is written in the measurement loop: the automatic thermalisation & the pilot run of the
+// automatic measurement interval are not checkpointed, a run stopped during them starts over from scratch.
+pub struct Checkpoint
+{
+    pub temperature: f64,
+    pub sweeps_done: usize,    // thermalisation + measurement sweeps
+    pub therm_steps: usize,    // sweeps before the first measurement (automatic thermalisation)
+    pub sweeps_per_measurement: usize, // (automatic interval)
+    pub rng_state: Vec<u8>,    // RngState::state of the run's generator
+    pub accumulators: Vec<f64>,
+    pub spins: IsingArray2D,
+}
+
+impl Checkpoint
+{
+    // "dir/out_rows=8,cols=8.txt" at T=2.25 => "dir/out_rows=8,cols=8_T=2.25.checkpoint"
+    pub fn file_name_for(output_file: &str, temp: f64) -> String
+    {
+        let path = Path::new(output_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        path.with_file_name(format!("{stem}_T={temp}.checkpoint")).to_string_lossy().into_owned()
+    }
+
+    // Written to a temporary file first, so that a run killed while writing keeps its previous checkpoint.
+    pub fn write_to_file(&self, file_name: &str) -> std::io::Result<()>
+    {
+        let tmp_file_name = format!("{file_name}.tmp");
+        {
+            let mut file = BufWriter::new(File::create(&tmp_file_name)?);
+            let (rows, cols) = self.spins.shape();
+
+            file.write_all(CHECKPOINT_MAGIC)?;
+            file.write_all(&(rows as u64).to_le_bytes())?;
+            file.write_all(&(cols as u64).to_le_bytes())?;
+            file.write_all(&self.temperature.to_le_bytes())?;
+            file.write_all(&(self.sweeps_done as u64).to_le_bytes())?;
+            file.write_all(&(self.therm_steps as u64).to_le_bytes())?;
+            file.write_all(&(self.sweeps_per_measurement as u64).to_le_bytes())?;
+            file.write_all(&(self.rng_state.len() as u64).to_le_bytes())?;
+            file.write_all(&self.rng_state)?;
+            file.write_all(&(self.accumulators.len() as u64).to_le_bytes())?;
+            for value in &self.accumulators
+            {
+                file.write_all(&value.to_le_bytes())?;
+            }
+            let spins: Vec<u8> = self.spins.as_slice().iter().map(|&s| s as u8).collect();
+            file.write_all(&spins)?;
+            file.flush()?;
+        }
+        std::fs::rename(&tmp_file_name, file_name)
+    }
+
+    pub fn read_from_file(file_name: &str) -> std::io::Result<Self>
+    {
+        let mut file = BufReader::new(File::open(file_name)?);
+
+        let mut magic = [0_u8; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC
+        {
+            return Err(std::io::Error::other(format!("{file_name} is not a checkpoint file")));
+        }
+        let rows         = read_u64(&mut file)? as usize;
+        let cols         = read_u64(&mut file)? as usize;
+        let temperature  = f64::from_bits(read_u64(&mut file)?);
+        let sweeps_done  = read_u64(&mut file)? as usize;
+        let therm_steps  = read_u64(&mut file)? as usize;
+        let sweeps_per_measurement = read_u64(&mut file)? as usize;
+        if sweeps_per_measurement == 0
+        {
+            return Err(std::io::Error::other(format!("{file_name}: no sweeps per measurement")));
+        }
+        let mut rng_state = vec![0_u8; read_u64(&mut file)? as usize];
+        file.read_exact(&mut rng_state)?;
+        let accumulators = (0..read_u64(&mut file)?)
+            .map(|_| read_u64(&mut file).map(f64::from_bits))
+            .collect::<std::io::Result<Vec<f64>>>()?;
+
+        let mut spins = vec![0_u8; rows*cols];
+        file.read_exact(&mut spins)?;
+        let spins = spins.into_iter().map(|s| s as i8).collect();
+        let spins = IsingArray2D::from_spins(rows, cols, spins).map_err(std::io::Error::other)?;
+
+        Ok(Self { temperature, sweeps_done, therm_steps, sweeps_per_measurement, rng_state, accumulators, spins })
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64>
+{
+    let mut bytes = [0_u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
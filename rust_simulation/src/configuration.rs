@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::swendsen_wang_algorithm::IsingArray2D;
+
+
+// Spin configuration of the first (or, without annealing, of every) temperature:
+//      Random:  independent random spins (infinite temperature), drawn from the temperature's own stream
+//      Up/Down: all spins +1 / -1 (zero temperature)
+//      Striped: upper half of the rows +1, lower half -1: two horizontal domain walls through the periodic lattice
+//      File:    configuration saved by write_configuration (or written by hand), see read_configuration
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum InitialState
+{
+    #[default]
+    Random,
+    Up,
+    Down,
+    Striped,
+    File(String),
+}
+
+impl InitialState
+{
+    pub fn name(&self) -> &str
+    {
+        match self
+        {
+            InitialState::Random          => "random",
+            InitialState::Up              => "up",
+            InitialState::Down            => "down",
+            InitialState::Striped         => "striped",
+            InitialState::File(file_name) => file_name,
+        }
+    }
+    // for the results header: the legacy header separates its values by commas, which the names of the saved
+    // configurations contain ("out_rows=8,cols=8_T=2.spins"): a file is given by its name only, commas replaced by ';'
+    pub fn description(&self) -> String
+    {
+        match self
+        {
+            InitialState::File(file_name) =>
+            {
+                let name = Path::new(file_name).file_name().and_then(|name| name.to_str()).unwrap_or(file_name);
+                format!("file {}", name.replace(',', ";"))
+            }
+            other => other.name().to_string(),
+        }
+    }
+    // None for Random, which needs the random stream of the temperature
+    pub fn configuration(&self, rows: usize, cols: usize) -> std::io::Result<Option<IsingArray2D>>
+    {
+        let uniform = |spin: i8| IsingArray2D::from_spins(rows, cols, vec![spin; rows*cols]).map_err(std::io::Error::other);
+        match self
+        {
+            InitialState::Random  => Ok(None),
+            InitialState::Up      => uniform(1).map(Some),
+            InitialState::Down    => uniform(-1).map(Some),
+            InitialState::Striped =>
+            {
+                let spins = (0..rows*cols).map(|site| if site / cols < rows / 2 { 1 } else { -1 }).collect();
+                IsingArray2D::from_spins(rows, cols, spins).map(Some).map_err(std::io::Error::other)
+            }
+            InitialState::File(file_name) =>
+            {
+                let spins = read_configuration(file_name)?;
+                let shape = spins.shape();
+                if shape != (rows as i32, cols as i32)
+                {
+                    return Err(std::io::Error::other(format!("{file_name}: {}x{} configuration, expected {rows}x{cols}", shape.0, shape.1)));
+                }
+                Ok(Some(spins))
+            }
+        }
+    }
+}
+
+// Anything else than the keywords is taken as the name of a configuration file
+impl std::str::FromStr for InitialState
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "random" | "hot"          => Ok(InitialState::Random),
+            "up" | "cold"             => Ok(InitialState::Up),
+            "down"                    => Ok(InitialState::Down),
+            "striped" | "domain_wall" => Ok(InitialState::Striped),
+            ""                        => Err("Empty initial state".to_string()),
+            _                         => Ok(InitialState::File(s.trim().to_string())),
+        }
+    }
+}
+
+
+// "dir/out_rows=8,cols=8.txt" at T=2.25 => "dir/out_rows=8,cols=8_T=2.25.spins"
+pub fn configuration_file_name_for(output_file: &str, temp: f64) -> String
+{
+    let path = Path::new(output_file);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+    path.with_file_name(format!("{stem}_T={temp}.spins")).to_string_lossy().into_owned()
+}
+
+// One line of '+' & '-' per row, after '#' comment lines:
+//      # temperature: 2.25
+//      ++++----
+//      +++-----
+pub fn write_configuration(file_name: &str, spins: &IsingArray2D, comments: &[(&str, String)]) -> std::io::Result<()>
+{
+    let mut file = BufWriter::new(File::create(file_name)?);
+    for (key, value) in comments
+    {
+        writeln!(&mut file, "# {key}: {value}")?;
+    }
+    let (_, cols) = spins.shape();
+    for row in spins.as_slice().chunks(cols as usize)
+    {
+        let line: String = row.iter().map(|&spin| if spin > 0 { '+' } else { '-' }).collect();
+        writeln!(&mut file, "{line}")?;
+    }
+    file.flush()
+}
+
+// Reads back write_configuration: every non comment line is a row, of '+' | '1' (up) & '-' | '0' (down) characters
+pub fn read_configuration(file_name: &str) -> std::io::Result<IsingArray2D>
+{
+    let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{file_name}: {msg}"));
+    let mut spins = Vec::new();
+    let mut shape = (0, 0);
+    for line in BufReader::new(File::open(file_name)?).lines()
+    {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#')
+        {
+            continue;
+        }
+        let row = line.chars().map(|c| match c
+        {
+            '+' | '1' => Ok(1_i8),
+            '-' | '0' => Ok(-1_i8),
+            other     => Err(invalid(format!("unexpected character '{other}' in row {}", shape.0 + 1))),
+        }).collect::<std::io::Result<Vec<i8>>>()?;
+        if shape.0 > 0 && row.len() != shape.1
+        {
+            return Err(invalid(format!("row {} has {} spins, expected {}", shape.0 + 1, row.len(), shape.1)));
+        }
+        shape = (shape.0 + 1, row.len());
+        spins.extend(row);
+    }
+    if shape.0 == 0
+    {
+        return Err(invalid("no spins".to_string()));
+    }
+    IsingArray2D::from_spins(shape.0, shape.1, spins).map_err(|err| invalid(err.to_string()))
+}
@@ -0,0 +1,158 @@
+use std::f64::consts::PI;
+
+use crate::monte_carlo_results::MonteCarloResults;
+use crate::swendsen_wang_algorithm::J;
+
+
+// Exact thermodynamics of a small periodic lattice, by enumerating its 2^N states once: the states are visited in
+// Gray code order (one spin flip from one state to the next) & summed per energy level, any temperature then only
+// costs a sum over the levels. The Hamiltonian is the one of the simulation, E = -J sum_i s_i (s_below + s_right):
+// 2N bonds, a lattice of 2 rows (or columns) having two bonds between the same pair of spins.
+// Global spin flips leave every quantity unchanged: only the states with the first spin up are visited.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExactEnumeration
+{
+    rows: usize,
+    cols: usize,
+    levels: Vec<Level>, // by increasing energy, occupied levels only
+}
+
+// Sums over the states of one energy level
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Level
+{
+    energy: f64,
+    states: f64,
+    abs_spin_sum: f64,  // sum of |M|
+    spin_sum_sqr: f64,  // sum of M²
+    spin_qx_sqr: f64,   // sum of |sigma_qx|², sigma_qx = sum_j s_j e^{i qx x_j} / sqrt(N), qx = 2 pi / cols
+}
+
+// Thermal averages at one temperature, per spin & normalised as the simulation's results
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExactResults
+{
+    pub temperature: f64,
+    pub log_partition_function: f64, // ln Z
+    pub energy_density: f64,         // <E> / N
+    pub specific_heat: f64,          // (<E²> - <E>²) / (N T²)
+    pub magnetisation: f64,          // <|m|>
+    pub magnetisation_sqr: f64,      // <m²>
+    pub susceptibility: f64,         // N (<m²> - <|m|>²) / T
+    pub susceptibility_m2: f64,      // N <m²> / T
+    pub structure_factor_q0: f64,    // S(0)  = <|sigma_0|²> = N <m²>
+    pub structure_factor_qx: f64,    // S(qx) = <|sigma_qx|²>
+    pub correlation_length: f64,     // sqrt(S(0) / S(qx) - 1) / qx, as StructureFactor
+}
+
+impl ExactEnumeration
+{
+    // 2^(MAX_SPINS - 1) states take well under a second (optimised)
+    pub const MAX_SPINS: usize = 25;
+
+    pub fn new(rows: usize, cols: usize) -> Result<Self, String>
+    {
+        let num_spins = rows * cols;
+        if rows < 2 || cols < 2 || num_spins > Self::MAX_SPINS
+        {
+            return Err(format!("Cannot enumerate a {rows}x{cols} lattice (at least 2x2, at most {} spins)", Self::MAX_SPINS));
+        }
+        let kernels: Vec<(f64, f64)> = (0..cols).map(|x| (2_f64 * PI * x as f64 / cols as f64).sin_cos()).collect();
+        let neighbours = |site: usize|
+        {
+            let (y, x) = (site / cols, site % cols);
+            [((y + 1) % rows) * cols + x, ((y + rows - 1) % rows) * cols + x, y * cols + (x + 1) % cols, y * cols + (x + cols - 1) % cols]
+        };
+
+        // all spins up, then one flip per state
+        let mut spins            = vec![1_i64; num_spins];
+        let mut column_sums      = vec![rows as i64; cols];
+        let mut spin_sum         = num_spins as i64;
+        let mut bond_sum         = 2 * num_spins as i64; // sum_i s_i (s_below + s_right)
+        let mut levels_by_energy = vec![Level::default(); 4 * num_spins + 1]; // bond sums -2N..=2N
+        for state in 0_u64..1 << (num_spins - 1)
+        {
+            if state > 0
+            {
+                let site          = 1 + state.trailing_zeros() as usize;
+                let spin          = spins[site];
+                bond_sum         -= 2 * spin * neighbours(site).iter().map(|&neighbour| spins[neighbour]).sum::<i64>();
+                spin_sum         -= 2 * spin;
+                column_sums[site % cols] -= 2 * spin;
+                spins[site]       = -spin;
+            }
+            let (re, im) = column_sums.iter().zip(&kernels).fold((0_f64, 0_f64), |(re, im), (&sum, &(sin, cos))| (re + sum as f64 * cos, im + sum as f64 * sin));
+            let level    = &mut levels_by_energy[(bond_sum + 2 * num_spins as i64) as usize];
+            // the state & its global flip
+            level.states       += 2_f64;
+            level.abs_spin_sum += 2_f64 * spin_sum.abs() as f64;
+            level.spin_sum_sqr += 2_f64 * (spin_sum * spin_sum) as f64;
+            level.spin_qx_sqr  += 2_f64 * (re * re + im * im) / num_spins as f64;
+        }
+        let levels = levels_by_energy.into_iter().enumerate()
+            .filter(|(_, level)| level.states > 0_f64)
+            .map(|(index, level)| Level { energy: -J * (index as f64 - 2_f64 * num_spins as f64), ..level })
+            .rev()
+            .collect();
+        Ok(Self { rows, cols, levels })
+    }
+    #[inline]
+    pub fn shape(&self) -> (usize, usize)
+    {
+        (self.rows, self.cols)
+    }
+    // (energy, number of states) of every occupied level, by increasing energy
+    pub fn density_of_states(&self) -> impl Iterator<Item = (f64, f64)> + '_
+    {
+        self.levels.iter().map(|level| (level.energy, level.states))
+    }
+    pub fn at(&self, temperature: f64) -> ExactResults
+    {
+        let num_spins = (self.rows * self.cols) as f64;
+        let ground    = self.levels[0].energy;
+        // Boltzmann weights relative to the ground state, which keeps them finite at low temperature
+        let weights: Vec<f64> = self.levels.iter().map(|level| (-(level.energy - ground) / temperature).exp()).collect();
+        let average   = |quantity: &dyn Fn(&Level) -> f64| self.levels.iter().zip(&weights).map(|(level, weight)| quantity(level) * weight).sum::<f64>();
+        let z         = average(&|level| level.states);
+
+        let energy     = average(&|level| level.energy * level.states) / z;
+        let energy_sqr = average(&|level| level.energy * level.energy * level.states) / z;
+        let abs_m      = average(&|level| level.abs_spin_sum) / (z * num_spins);
+        let m_sqr      = average(&|level| level.spin_sum_sqr) / (z * num_spins * num_spins);
+        let s_q0       = num_spins * m_sqr;
+        let s_qx       = average(&|level| level.spin_qx_sqr) / z;
+        let qx         = 2_f64 * PI / self.cols as f64;
+
+        ExactResults
+        {
+            temperature,
+            log_partition_function: z.ln() - ground / temperature,
+            energy_density: energy / num_spins,
+            specific_heat: (energy_sqr - energy * energy) / (num_spins * temperature * temperature),
+            magnetisation: abs_m,
+            magnetisation_sqr: m_sqr,
+            susceptibility: num_spins * (m_sqr - abs_m * abs_m) / temperature,
+            susceptibility_m2: num_spins * m_sqr / temperature,
+            structure_factor_q0: s_q0,
+            structure_factor_qx: s_qx,
+            correlation_length: (s_q0 / s_qx - 1_f64).abs().sqrt() / qx,
+        }
+    }
+}
+
+impl ExactResults
+{
+    // named as the simulation's columns, for comparisons
+    pub fn results(&self) -> MonteCarloResults<f64>
+    {
+        let mut results = MonteCarloResults::new();
+        results.push("energy_density",         self.energy_density);
+        results.push("magnetisation",          self.magnetisation);
+        results.push("specific_heat",          self.specific_heat);
+        results.push("susceptibility",         self.susceptibility);
+        results.push("susceptibility_m2",      self.susceptibility_m2);
+        results.push("correlation length",     self.correlation_length);
+        results.push("log_partition_function", self.log_partition_function);
+        results
+    }
+}
@@ -1,2 +1,13 @@
 pub mod swendsen_wang_algorithm;
-pub mod monte_carlo_results;
\ No newline at end of file
+pub mod monte_carlo_results;
+pub mod observables;
+pub mod time_series;
+pub mod checkpoint;
+pub mod configuration;
+pub mod exact;
+pub mod animation;
+pub mod random;
+pub mod simulation;
+pub mod snapshot;
+pub mod statistics;
+pub mod thermalisation;
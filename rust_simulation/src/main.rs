@@ -2,79 +2,21 @@
 
 use std::{usize};
 use num::{Zero};
-use rand::{rngs, SeedableRng};
 use std::env;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use rayon::{self};
-use num::complex::ComplexFloat;
 
-use swendsen_wang::monte_carlo_results::MonteCarloResults;
-use swendsen_wang::swendsen_wang_algorithm::{SwendsenWangAlgorithm, IsingArray2D};
+use swendsen_wang::monte_carlo_results::{MonteCarloResults, OutputFormat, ResultsFile};
+use swendsen_wang::simulation::{Simulation, Sampler, PrecisionTarget, MeasurementInterval};
+use swendsen_wang::swendsen_wang_algorithm::{LatticeBackend, UnionFindStrategy, LabellingStrategy, BondActivation};
+use swendsen_wang::time_series::TimeSeriesFormat;
+use swendsen_wang::thermalisation::Thermalisation;
+use swendsen_wang::configuration::InitialState;
+use swendsen_wang::snapshot::Snapshots;
+use swendsen_wang::animation::Animation;
+use swendsen_wang::random::RngKind;
 use parameter_reader::ParameterReader;
 
 
 const MINIMUM_TEMP: f64 = 1E-6;
-use swendsen_wang::swendsen_wang_algorithm::J;
-
- 
-fn perform_swendsen_wang_monte_carlo(rows: usize, cols: usize, temperatures: Vec<f64>, therm_steps: usize, measure_steps: usize, measure_corr_length: bool) -> Vec<MonteCarloResults<f64>>
-{
-    let mut results = vec![MonteCarloResults::<f64>::default(); temperatures.len()];
-
-    (&temperatures, &mut results).into_par_iter().for_each(|(&temp, result)| 
-    {
-        let mut rng           = rngs::SmallRng::from_os_rng();
-        let mut spins         = IsingArray2D::new_randomized(&mut rng, rows, cols);
-        let mut swendsen_wang = SwendsenWangAlgorithm::new(rows, cols);
-
-        let proba_add = 1f64 - (-2_f64*J/temp).exp();
-        
-        swendsen_wang.take_fourier_transform = false;
-        for _ in 0..therm_steps
-        {
-            swendsen_wang.perform_swendsen_wang_all(&mut spins, &mut rng, proba_add);
-            swendsen_wang.flip_cluster_and_take_fourier(&mut spins, &mut rng);
-            swendsen_wang.reset();
-        }
-
-        let mut energy_acc     = 0_f64;
-        let mut energy_sqr_acc = 0_f64;
-        let mut spin_sum_acc   = 0_f64;
-        let mut spin_sqr_acc   = 0_f64;
-        let mut re_spin_q0_sqr_acc = 0_f64; //  <Re[sigma_q0]²>  
-        let mut re_spin_qx_sqr_acc = 0_f64; //  <Re[sigma_qx]²>  
-        let mut im_spin_qx_sqr_acc = 0_f64; //  <Im[sigma_qx]²> 
-
-        swendsen_wang.take_fourier_transform = measure_corr_length;
-        for _ in 0..measure_steps
-        {
-            let (energy, spin_sum) = swendsen_wang.perform_swendsen_wang_all(&mut spins, &mut rng, proba_add);
-            let (spin_q0, spin_qx) = swendsen_wang.flip_cluster_and_take_fourier(&mut spins, &mut rng);
-            swendsen_wang.reset();
-
-            energy_acc     += energy;
-            energy_sqr_acc += energy*energy;
-            spin_sum_acc   += spin_sum;
-            spin_sqr_acc   += spin_sum*spin_sum;
-
-            // Structure factor calculation
-            if swendsen_wang.take_fourier_transform
-            {
-                re_spin_q0_sqr_acc += spin_q0*spin_q0;
-                re_spin_qx_sqr_acc += spin_qx.re()*spin_qx.re();
-                im_spin_qx_sqr_acc += spin_qx.im()*spin_qx.im();
-            }            
-        }
-        result.struct_fact_q0 = re_spin_q0_sqr_acc/(measure_steps as f64);                          //S(q0) =  <Re[sigma_q0]²>
-        result.struct_fact_qx = (re_spin_qx_sqr_acc + im_spin_qx_sqr_acc)/(measure_steps as f64);   //S(qx) =  <Re[sigma_qx]²> + <Im[sigma_qx]²>
-        result.spins_sum_avg  = spin_sum_acc/(measure_steps as f64);
-        result.spins_sqr_avg  = spin_sqr_acc/(measure_steps as f64);
-        result.energy_avg     = energy_acc/(measure_steps as f64);
-        result.energy_sqr_avg = energy_sqr_acc/(measure_steps as f64);
-    });
-
-    results
-}
 
 
 const PARAMETERS: [&'static str; 7] = [
@@ -86,13 +28,148 @@ const PARAMETERS: [&'static str; 7] = [
     "temperatures",
     "measure_struct_fact"
 ];
+
+// Parameters which may be left out of the parameter file:
+//      thermalisation: fixed | auto        (default: fixed) auto: stop thermalising once a hot & a cold start agree, therm_steps at most
+//      sweeps_per_measurement: <n> | auto  (default: 1) measure_steps measurements n sweeps apart, auto: 2 x the autocorrelation time
+//      fourier_interval: <n>               (default: 1) Fourier transform (structure factor) every n-th measurement only
+//      time_series: none | csv | binary    (default: none) raw per-sweep measurements, one file per temperature
+//      checkpoint_interval: <sweeps>       (default: 0 = off)  checkpoint every temperature after this many sweeps
+//                                          (not during the automatic thermalisation & interval pilot, which start over)
+//      seed: <u64>                         (default: drawn from the OS) master seed, recorded in the output header
+//      rng: small | chacha | mt64 | pcg    (default: small) random generator, recorded in the output header
+//      lattice_backend: standard | compact (default: standard) compact: 1 bit per spin & 32-bit cluster labels
+//      strips: <n>                         (default: 1) > 1: cut the lattice in n strips of rows labelled in parallel,
+//                                          each by its own union-find: labelling & union_find must be left to their defaults
+//      union_find: smallest_label | by_size (default: smallest_label) merging rule of the (sequential) Hoshen-Kopelman labelling
+//      labelling: hoshen_kopelman | union_find | flood_fill | label_equivalence (default: hoshen_kopelman) cluster identification
+//      bond_activation: per_bond | geometric_skip (default: per_bond) geometric_skip: one random number per rare bond outcome
+//      output_format: legacy | csv | json | npy | npz (default: legacy) other formats replace the extension of outputfile,
+//                                          npy writes the run parameters to <outputfile stem>_metadata.json
+//      magnetisation_bins: <n>             (default: 0 = off) histogram P(m) of the magnetisation per spin, one csv file per temperature
+//      energy_bins: <n>                    (default: 0 = off) histogram P(E) of the energy per spin, one csv file per temperature
+//      target_precision: <result>=<relative error>, ... (default: none) e.g. specific_heat=0.005: measure_steps becomes a minimum,
+//                                          sweeping until every error (binning + jackknife) is reached
+//      max_measure_steps: <sweeps>         (default: 10 x measure_steps) with target_precision: measurement budget per temperature
+//      time_limit: <seconds>               (default: none) with target_precision: wall-clock budget per temperature
+//      initial_state: random | up | down | striped | <configuration file> (default: random) start of every temperature
+//      anneal: true | false                (default: false) temperatures in the given order, each starting from the last configuration of the previous one
+//      save_configurations: true | false   (default: false) final configuration of every temperature, "+-" text next to outputfile
+//      snapshot_interval: <sweeps>         (default: 0 = off) image of the spins every n sweeps after the thermalisation, next to outputfile
+//      snapshot_temperatures: <t1>, <t2>.. (default: all) temperatures to take snapshots at
+//      snapshot_clusters: true | false     (default: false) also draw the Fortuin-Kasteleyn clusters of the snapshot sweeps
+//      snapshot_format: netpbm | png       (default: netpbm) netpbm: .pgm spins & .ppm clusters
+//      snapshot_scale: <pixels>            (default: 1) pixels per spin (side)
+fn parse_optional_parameter<T>(reader: &ParameterReader, name: &str) -> Option<T> where T: std::str::FromStr, T::Err: std::fmt::Display
+{
+    let params = reader.parse_parameters(&[name], ":").ok()?;
+    let value  = params[name].parse().unwrap_or_else(|err|
+    {
+        println!("!! Could not parse \"{name}\": {err}");
+        std::process::exit(1);
+    });
+    Some(value)
+}
+
+// merge merged.txt run_1.txt run_2.txt ...: combines runs of the same lattice size, see ResultsFile::merge.
+// The runs may be in any output format, the merged file is written in the format given by its extension (legacy by default).
+fn merge_files(files: &[String])
+{
+    let [merged_file, run_files @ ..] = files else
+    {
+        println!("Usage: merge merged.txt run_1.txt run_2.txt ...");
+        std::process::exit(1);
+    };
+    let runs = run_files.iter().map(|file_name| MonteCarloResults::<f64>::read_from_file(file_name).unwrap_or_else(|err|
+    {
+        println!("!! Could not read {file_name}: {err}");
+        std::process::exit(1);
+    })).collect::<Vec<_>>();
+
+    let merged = ResultsFile::merge(&runs).unwrap_or_else(|err|
+    {
+        println!("!! Could not merge: {err}");
+        std::process::exit(1);
+    });
+    let format = std::path::Path::new(merged_file).extension()
+        .and_then(|extension| extension.to_str()?.parse().ok())
+        .unwrap_or_default();
+    merged.write_as(format, merged_file).unwrap_or_else(|err|
+    {
+        println!("Could not write to file: {err}");
+        std::process::exit(1);
+    });
+    println!("Merged {} runs ({} temperatures) into {merged_file}", runs.len(), merged.temperatures.len());
+}
+
+// animate animation.txt: GIF of the Swendsen-Wang updates at one temperature, see Animation. The parameter file gives
+//      rows, cols, temperature, steps, outputfile (the .gif)
+// & optionally therm_steps (default: 0), show_clusters (default: false) the clusters before every flip,
+// frame_delay (default: 50) in hundredths of a second, scale (default: 4) pixels per spin, seed & initial_state.
+fn animate(files: &[String])
+{
+    let [parameter_file] = files else
+    {
+        println!("Usage: animate animation.txt");
+        std::process::exit(1);
+    };
+    let reader = ParameterReader::build(parameter_file).unwrap_or_else(|err|
+    {
+        println!("Could not build reader: {err}");
+        std::process::exit(1);
+    });
+    let params = reader.parse_parameters(&["rows", "cols", "temperature", "steps", "outputfile"], ":").unwrap_or_else(|err|
+    {
+        println!("Missing parameters / failed to parse line: {err}");
+        std::process::exit(1);
+    });
+    let defaults      = Animation::default();
+    let show_clusters = parse_optional_parameter::<String>(&reader, "show_clusters")
+        .is_some_and(|flag| flag.to_lowercase().parse().expect("!! Could not parse \"show_clusters\" (true or false)"));
+    let animation     = Animation
+    {
+        rows: params["rows"].parse().expect("!! Could not parse \"rows\""),
+        cols: params["cols"].parse().expect("!! Could not parse \"cols\""),
+        temperature: params["temperature"].parse().expect("!! Could not parse \"temperature\""),
+        steps: params["steps"].parse().expect("!! Could not parse \"steps\""),
+        therm_steps: parse_optional_parameter(&reader, "therm_steps").unwrap_or(defaults.therm_steps),
+        show_clusters,
+        scale: parse_optional_parameter(&reader, "scale").unwrap_or(defaults.scale),
+        frame_delay: parse_optional_parameter(&reader, "frame_delay").unwrap_or(defaults.frame_delay),
+        seed: parse_optional_parameter(&reader, "seed").unwrap_or_else(rand::random),
+        initial_state: parse_optional_parameter(&reader, "initial_state").unwrap_or_default(),
+    };
+    let output_file = &params["outputfile"];
+    println!("Animating {} Swendsen-Wang steps of a {}x{} lattice at T={} (seed {})", animation.steps, animation.rows, animation.cols, animation.temperature, animation.seed);
+    let frames = animation.write_to_file(output_file).unwrap_or_else(|err|
+    {
+        println!("!! Could not animate: {err}");
+        std::process::exit(1);
+    });
+    println!("{frames} frames saved as {output_file}");
+}
+
 fn main() 
 {
     let args   = env::args().collect::<Vec<_>>();
     if args.len() < 2
     {
-        println!("Not enough arguments: Usage: {} parameter.txt", &args[0]);
+        println!("Not enough arguments: Usage: {} parameter.txt [--resume]", &args[0]);
+        println!("                   or: {} merge merged.txt run_1.txt run_2.txt ...", &args[0]);
+        println!("                   or: {} animate animation.txt", &args[0]);
+        std::process::exit(1);
+    }
+    if args[1] == "merge"
+    {
+        merge_files(&args[2..]);
+        return;
     }
+    if args[1] == "animate"
+    {
+        animate(&args[2..]);
+        return;
+    }
+    let resume = args[2..].iter().any(|arg| arg == "--resume");
 
     let reader = ParameterReader::build(&args[1]).unwrap_or_else(|err|
     {   
@@ -113,6 +190,52 @@ fn main()
     let measure_steps: usize       = params["measure_steps"].parse().expect("!! Could not parse \"measure_steps\"");
     let mut temperatures: Vec<f64> = params["temperatures"].split(",").map(|t| t.trim().parse().expect("!! failed parse temperatures") ).collect();
     let measure_struct_fact: bool  = params["measure_struct_fact"].to_lowercase().parse().expect("!! Could not parse structur factor");
+    let time_series_format         = parse_optional_parameter::<String>(&reader, "time_series")
+        .filter(|format| format.trim().to_lowercase() != "none")
+        .map(|format| format.parse::<TimeSeriesFormat>().unwrap_or_else(|err|
+        {
+            println!("!! {err}");
+            std::process::exit(1);
+        }));
+    let checkpoint_interval: usize = parse_optional_parameter(&reader, "checkpoint_interval").unwrap_or(0);
+    let thermalisation: Thermalisation = parse_optional_parameter(&reader, "thermalisation").unwrap_or_default();
+    let measurement_interval: MeasurementInterval = parse_optional_parameter(&reader, "sweeps_per_measurement").unwrap_or_default();
+    let fourier_interval: usize    = parse_optional_parameter(&reader, "fourier_interval").unwrap_or(1);
+    let seed: u64                  = parse_optional_parameter(&reader, "seed").unwrap_or_else(rand::random);
+    let rng: RngKind               = parse_optional_parameter(&reader, "rng").unwrap_or_default();
+    let lattice_backend: LatticeBackend = parse_optional_parameter(&reader, "lattice_backend").unwrap_or_default();
+    let strips: usize              = parse_optional_parameter(&reader, "strips").unwrap_or(1);
+    let union_find: UnionFindStrategy = parse_optional_parameter(&reader, "union_find").unwrap_or_default();
+    let labelling: LabellingStrategy  = parse_optional_parameter(&reader, "labelling").unwrap_or_default();
+    let bond_activation: BondActivation = parse_optional_parameter(&reader, "bond_activation").unwrap_or_default();
+    let output_format: OutputFormat     = parse_optional_parameter(&reader, "output_format").unwrap_or_default();
+    let magnetisation_bins: usize  = parse_optional_parameter(&reader, "magnetisation_bins").unwrap_or(0);
+    let energy_bins: usize         = parse_optional_parameter(&reader, "energy_bins").unwrap_or(0);
+    let initial_state: InitialState = parse_optional_parameter(&reader, "initial_state").unwrap_or_default();
+    let parse_flag                 = |name: &str| parse_optional_parameter::<String>(&reader, name)
+        .is_some_and(|flag| flag.to_lowercase().parse().unwrap_or_else(|_| panic!("!! Could not parse \"{name}\" (true or false)")));
+    let anneal                     = parse_flag("anneal");
+    let save_configurations        = parse_flag("save_configurations");
+    let snapshots                  = parse_optional_parameter::<usize>(&reader, "snapshot_interval").filter(|&interval| interval > 0).map(|interval|
+    {
+        let temperatures = parse_optional_parameter::<String>(&reader, "snapshot_temperatures").map_or(Vec::new(), |temperatures|
+            temperatures.split(",").map(|t| t.trim().parse().expect("!! failed parse snapshot_temperatures")).collect());
+        let clusters     = parse_flag("snapshot_clusters");
+        let format       = parse_optional_parameter(&reader, "snapshot_format").unwrap_or_default();
+        let scale        = parse_optional_parameter(&reader, "snapshot_scale").unwrap_or(1);
+        Snapshots { interval, temperatures, clusters, format, scale }
+    });
+    let precision                  = parse_optional_parameter::<String>(&reader, "target_precision").map(|targets|
+    {
+        let targets = PrecisionTarget::parse_targets(&targets).unwrap_or_else(|err|
+        {
+            println!("!! {err}");
+            std::process::exit(1);
+        });
+        let max_measure_steps = parse_optional_parameter(&reader, "max_measure_steps").unwrap_or(10 * measure_steps);
+        let time_limit        = parse_optional_parameter(&reader, "time_limit").map(std::time::Duration::from_secs_f64);
+        PrecisionTarget { targets, max_measure_steps, time_limit }
+    });
 
     temperatures
         .iter_mut()
@@ -132,19 +255,104 @@ fn main()
     let temp_len  = temperatures.len();
     println!("Using: {temp_len} temperatures values from {temp_first} to {temp_last}");
     println!("Measuring correlation length: {measure_struct_fact}");
+    println!("Initial state: {}{}", initial_state.name(), if anneal { ", annealing from one temperature to the next" } else { "" });
+    println!("Sweeps per measurement: {measurement_interval}, Fourier transform every {} measurement(s)", fourier_interval.max(1));
+    if thermalisation == Thermalisation::Auto
+    {
+        println!("Automatic thermalisation: at most {therm_steps} sweeps, the sweeps used are written to the results");
+    }
+    println!("Master seed: {seed} ({} generator)", rng.name());
+    println!("Lattice backend: {}", lattice_backend.name());
+    println!("Bond activation: {}", bond_activation.name());
+    println!("Output format: {}", output_format.name());
+    match strips
+    {
+        0 | 1  => println!("Labelling: {}, union-find strategy: {}", labelling.name(), union_find.name()),
+        strips => println!("Labelling {strips} strips in parallel per temperature"),
+    }
+    if let Some(format) = time_series_format
+    {
+        println!("Writing per-sweep time series as {format:?}");
+    }
+    if checkpoint_interval > 0
+    {
+        println!("Checkpointing every {checkpoint_interval} sweeps");
+    }
+    if let Some(precision) = precision.as_ref()
+    {
+        let time_limit = precision.time_limit.map_or("none".to_string(), |limit| format!("{}s", limit.as_secs_f64()));
+        println!("Target precision: {} (at most {} measurements & {time_limit} per temperature)", precision.targets_description(), precision.max_measure_steps);
+    }
+    if magnetisation_bins > 0 || energy_bins > 0
+    {
+        println!("Histograms: {magnetisation_bins} magnetisation bins, {energy_bins} energy bins (0 = off)");
+    }
+    if let Some(snapshots) = snapshots.as_ref()
+    {
+        println!("Snapshots every {} sweeps at T: {} ({}{})", snapshots.interval, snapshots.temperatures_description(), snapshots.format.name(),
+            if snapshots.clusters { ", with the clusters" } else { "" });
+    }
+    if resume
+    {
+        println!("Resuming from checkpoints (temperatures without one start over)");
+    }
+
+    let sampler        = Sampler { lattice_backend, strips, union_find, labelling, bond_activation };
+    let mut simulation = Simulation::builder()
+        .lattice(rows, cols)
+        .temperatures(&temperatures)
+        .sweeps(therm_steps, measure_steps)
+        .thermalisation(thermalisation)
+        .sweeps_per_measurement(measurement_interval)
+        .fourier_interval(fourier_interval)
+        .measure_structure_factor(measure_struct_fact)
+        .seed(seed)
+        .rng(rng)
+        .sampler(sampler)
+        .output_file(&outputfile)
+        .checkpoint_interval(checkpoint_interval)
+        .resume(resume)
+        .histograms(magnetisation_bins, energy_bins)
+        .initial_state(initial_state)
+        .anneal(anneal)
+        .save_configurations(save_configurations);
+    if let Some(format) = time_series_format
+    {
+        simulation = simulation.time_series(format);
+    }
+    if let Some(precision) = precision
+    {
+        simulation = simulation.target_precision(precision);
+    }
+    if let Some(snapshots) = snapshots
+    {
+        simulation = simulation.snapshots(snapshots);
+    }
+    let simulation = simulation.build().unwrap_or_else(|err|
+    {
+        println!("!! {err}");
+        std::process::exit(1);
+    });
 
     let time = std::time::SystemTime::now();
-    let results: Vec<MonteCarloResults<f64>> = perform_swendsen_wang_monte_carlo(rows, cols, temperatures.clone(), therm_steps, measure_steps, measure_struct_fact);
+    let results: Vec<MonteCarloResults<f64>> = simulation.run();
     let elapsed_time = time.elapsed().unwrap();
     
     println!("Time taken: {}s", elapsed_time.as_secs());
     
 
-    MonteCarloResults::write_to_file(&outputfile, &temperatures, &results, rows, cols, elapsed_time).unwrap_or_else(|err|
+    let result_file = output_format.file_name_for(&outputfile);
+    let run_info    = simulation.run_parameters();
+    MonteCarloResults::write_as(output_format, &result_file, &temperatures, &results, elapsed_time, &run_info).unwrap_or_else(|err|
     {
         print!("Could not write to file: {err}");
         std::process::exit(1);
     });
-    println!("File saved as {outputfile}");
+    println!("File saved as {result_file}");
+
+    if checkpoint_interval > 0
+    {
+        simulation.remove_checkpoints();
+    }
 
 }   
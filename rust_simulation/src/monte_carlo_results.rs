@@ -1,23 +1,68 @@
 use num_traits::Float;
-use std::f64::consts::PI;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::iter::zip;
 
-#[derive(Debug, Default, Clone, Copy)]
+mod output_format;
+mod reader;
+mod merge;
+
+pub use output_format::OutputFormat;
+pub use merge::MEASUREMENTS_COLUMN;
+pub use reader::ResultsFile;
+
+
+// Named scalar results of one temperature, in the order the observables reported them
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct MonteCarloResults<T> where T: Float
 {
-    pub spins_sum_avg: T,   
-    pub spins_sqr_avg: T,  
-    pub energy_avg: T,       
-    pub energy_sqr_avg: T,  
-    pub struct_fact_q0: T,
-    pub struct_fact_qx: T,      
+    values: Vec<(String, T)>,
+}
+
+impl<T> MonteCarloResults<T> where T: Float
+{
+    pub fn new() -> Self
+    {
+        Self { values: Vec::new() }
+    }
+    pub fn push(&mut self, name: &str, value: T)
+    {
+        self.values.push((name.to_string(), value));
+    }
+    pub fn get(&self, name: &str) -> Option<T>
+    {
+        self.values.iter().find(|(key, _)| key == name).map(|&(_, value)| value)
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str>
+    {
+        self.values.iter().map(|(name, _)| name.as_str())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, T)>
+    {
+        self.values.iter().map(|(name, value)| (name.as_str(), *value))
+    }
+    pub fn len(&self) -> usize
+    {
+        self.values.len()
+    }
+    pub fn is_empty(&self) -> bool
+    {
+        self.values.is_empty()
+    }
+}
+
+impl<T> FromIterator<(String, T)> for MonteCarloResults<T> where T: Float
+{
+    fn from_iter<I: IntoIterator<Item = (String, T)>>(iter: I) -> Self
+    {
+        Self { values: iter.into_iter().collect() }
+    }
 }
 
 
 impl<T> MonteCarloResults<T> where T: Float + std::fmt::Display
 {
-    pub fn write_to_file(file_name: &String, temperatures: &[T], results: &[MonteCarloResults<T>], rows: usize, cols: usize, elapsed_time: std::time::Duration ) -> std::io::Result<()>
+    // Column names ("temp" first) & one row of values per temperature
+    fn table<'a>(temperatures: &[T], results: &'a [MonteCarloResults<T>]) -> std::io::Result<(Vec<&'a str>, Vec<Vec<T>>)>
     {
         if temperatures.len() != results.len()
         {
@@ -27,30 +72,92 @@ impl<T> MonteCarloResults<T> where T: Float + std::fmt::Display
         {
             return Err(std::io::Error::other("Temperatures should be positive"));
         }
-        
+        let names: Vec<&str> = results.first().map(|res| res.names().collect()).unwrap_or_default();
+        if results.iter().any(|res| !res.names().eq(names.iter().copied()))
+        {
+            return Err(std::io::Error::other("Every temperature should have the same results"));
+        }
 
-        let mut file= std::fs::File::create(file_name)?;
-        writeln!(&mut file, "temp, energy_density, magnetisation, specific_heat, susceptibility, correlation length, elapsed_time: {}", elapsed_time.as_secs())?;
+        let columns = std::iter::once("temp").chain(names).collect();
+        let rows    = zip(temperatures, results)
+            .map(|(&temp, res)| std::iter::once(temp).chain(res.iter().map(|(_, value)| value)).collect())
+            .collect();
+        Ok((columns, rows))
+    }
+
+    // One line per temperature: "temp" followed by the results of every observable, for ex. with the default ones:
+    //      temp, energy_density, magnetisation, specific_heat, susceptibility, correlation length, elapsed_time: 46, seed: 42
+    pub fn write_to_file(file_name: &str, temperatures: &[T], results: &[MonteCarloResults<T>], elapsed_time: std::time::Duration, run_info: &[(&str, String)]) -> std::io::Result<()>
+    {
+        Self::write_as(OutputFormat::Legacy, file_name, temperatures, results, elapsed_time, run_info)
+    }
 
-        let qx        = 2_f64 * PI / cols as f64;
-        let num_spins = T::from(rows*cols).unwrap();
+    // run_info: run parameters stored as metadata, after the elapsed time (see OutputFormat for where they go)
+    pub fn write_as(format: OutputFormat, file_name: &str, temperatures: &[T], results: &[MonteCarloResults<T>], elapsed_time: std::time::Duration, run_info: &[(&str, String)]) -> std::io::Result<()>
+    {
+        // whole seconds in legacy files, as the notebooks expect
+        let elapsed_time = match format
+        {
+            OutputFormat::Legacy => elapsed_time.as_secs().to_string(),
+            _                    => elapsed_time.as_secs_f64().to_string(),
+        };
+        let metadata: Vec<(String, String)> = std::iter::once(("elapsed_time".to_string(), elapsed_time))
+            .chain(run_info.iter().map(|(key, value)| (key.to_string(), value.clone())))
+            .collect();
+        Self::write_with_metadata(format, file_name, temperatures, results, &metadata)
+    }
 
-        for  (&temp, res) in zip(temperatures, results)
+    // metadata: (key, value) pairs written as they are. The legacy header separates them by commas & keys from values by ':':
+    // a comma in a key or value, or a ':' in a key, could not be read back and is an InvalidInput error (nothing is written).
+    pub fn write_with_metadata(format: OutputFormat, file_name: &str, temperatures: &[T], results: &[MonteCarloResults<T>], metadata: &[(String, String)]) -> std::io::Result<()>
+    {
+        let (columns, rows) = Self::table(temperatures, results)?;
+        let unreadable      = |(key, value): &&(String, String)| key.contains([',', ':']) || value.contains(',');
+        if let Some((key, value)) = metadata.iter().find(unreadable).filter(|_| format == OutputFormat::Legacy)
         {
-            let specific_heat   = (res.energy_sqr_avg - res.energy_avg.powi(2) ) / (temp.powi(2) * num_spins);
-            let energy_density  = res.energy_avg / num_spins;
-            let magnetisation   = res.spins_sum_avg / num_spins;
-            let susceptibility  = (res.spins_sqr_avg - res.spins_sum_avg.powi(2)) / (temp * num_spins);
-            let mut corr_length = T::zero();
-            if !res.struct_fact_q0.is_zero() && !res.struct_fact_qx.is_zero()
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("legacy metadata \"{key}: {value}\" contains a separator")));
+        }
+        let mut file        = BufWriter::new(std::fs::File::create(file_name)?);
+
+        match format
+        {
+            OutputFormat::Legacy =>
+            {
+                // metadata is appended to the header as ", key: value", for ex: ", elapsed_time: 46, seed: 42"
+                let metadata = metadata.iter().map(|(key, value)| format!(", {key}: {value}")).collect::<String>();
+                writeln!(&mut file, "{}{metadata}", columns.join(", "))?;
+                for row in &rows
+                {
+                    let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+                    writeln!(&mut file, "{}", values.join(", "))?;
+                }
+            }
+            OutputFormat::Csv  => output_format::write_csv(&mut file, &columns, &rows, metadata)?,
+            OutputFormat::Json => output_format::write_json(&mut file, &columns, &rows, metadata)?,
+            OutputFormat::Npy  =>
+            {
+                file.write_all(&output_format::results_npy(&columns, &rows))?;
+                let mut metadata_file = BufWriter::new(std::fs::File::create(OutputFormat::metadata_file_name_for(file_name))?);
+                output_format::write_json_metadata(&mut metadata_file, metadata)?;
+                metadata_file.flush()?;
+            }
+            OutputFormat::Npz  =>
             {
-                let sf_ratio  = (res.struct_fact_q0/res.struct_fact_qx - T::one()).abs().sqrt();
-                corr_length   = sf_ratio / T::from(qx).unwrap();
+                let files = [("results.npy", output_format::results_npy(&columns, &rows)), ("metadata.npy", output_format::metadata_npy(metadata))];
+                output_format::write_zip(&mut file, &files)?;
             }
-            writeln!(&mut file, "{temp}, {energy_density}, {magnetisation}, {specific_heat}, {susceptibility}, {corr_length}")?;
         }
-    
-        Ok(())
+        file.flush()
+    }
+}
+
+impl<T> MonteCarloResults<T> where T: Float
+{
+    // Reads back a file written by write_to_file / write_as in any format, the format being recognised from the content.
+    // Older legacy files only carry the elapsed time: see ResultsFile::shape for their lattice size.
+    pub fn read_from_file(file_name: &str) -> std::io::Result<ResultsFile<T>>
+    {
+        reader::read(file_name)
     }
 }
 
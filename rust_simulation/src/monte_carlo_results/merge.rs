@@ -0,0 +1,133 @@
+use num_traits::Float;
+use std::cmp::Ordering;
+
+use super::{MonteCarloResults, ResultsFile};
+
+
+// Number of measurements behind every temperature of a merged file
+pub const MEASUREMENTS_COLUMN: &str = "measurements";
+
+impl<T> ResultsFile<T> where T: Float
+{
+    // Combines independent runs of the same lattice (other seeds, other machines, overlapping temperature lists).
+    // Each run is weighted by its number of measurements: the "measurements" column of an already merged file,
+    // or else its measure_steps parameter (equal weights if no file records it, older legacy files).
+    // At every temperature, with weights w = n / sum(n):
+    //      every result:              sum(w x)
+    //      specific_heat:             sum(w C) + N sum(w (e - <e>)²) / T²
+    //      susceptibility:            sum(w chi) + N sum(w (m - <m>)²) / T
+    //      "_err" columns:            sqrt(sum(w² err²))
+    // i.e. specific heat & susceptibility are the variances of the pooled samples, the scatter between the runs included.
+    // Metadata common to every run is kept, differing values are joined with ';' (seeds...), elapsed times are added.
+    pub fn merge(files: &[ResultsFile<T>]) -> Result<Self, String>
+    {
+        let first = files.first().ok_or("nothing to merge")?;
+
+        let shape = first.shape().ok_or_else(|| format!("{}: unknown lattice size", first.file_name))?;
+        if let Some(other) = files.iter().find(|file| file.shape() != Some(shape))
+        {
+            let size = other.shape().map_or("unknown".to_string(), |size| format!("{size:?}"));
+            return Err(format!("{}: lattice size {size}, expected {shape:?}", other.file_name));
+        }
+        let num_spins = T::from(shape.0 * shape.1).unwrap();
+
+        let names: Vec<&str> = files.iter().flat_map(|file| file.results.first()).next()
+            .map(|res| res.names().filter(|&name| name != MEASUREMENTS_COLUMN).collect())
+            .unwrap_or_default();
+        for file in files
+        {
+            if file.results.iter().any(|res| !res.names().filter(|&name| name != MEASUREMENTS_COLUMN).eq(names.iter().copied()))
+            {
+                return Err(format!("{}: results differ from {:?}", file.file_name, names));
+            }
+        }
+
+        let measure_steps: Vec<Option<usize>> = files.iter().map(|file| file.parse_metadata("measure_steps")).collect();
+        let default_steps = match measure_steps.iter().all(Option::is_none)
+        {
+            true  => Some(1),
+            false => None,
+        };
+        let mut samples = Vec::new(); // (temperature, measurements, results)
+        for (file, steps) in files.iter().zip(&measure_steps)
+        {
+            for (&temp, res) in file.temperatures.iter().zip(&file.results)
+            {
+                let measurements = res.get(MEASUREMENTS_COLUMN)
+                    .or_else(|| steps.or(default_steps).and_then(T::from))
+                    .ok_or_else(|| format!("{}: no measure_steps, cannot weight it against the other runs", file.file_name))?;
+                samples.push((temp, measurements, res));
+            }
+        }
+        samples.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        let mut temperatures = Vec::new();
+        let mut results      = Vec::new();
+        for group in samples.chunk_by(|a, b| a.0 == b.0)
+        {
+            let temp         = group[0].0;
+            let measurements = group.iter().fold(T::zero(), |total, &(_, n, _)| total + n);
+            let weights: Vec<T> = group.iter().map(|&(_, n, _)| n / measurements).collect();
+
+            let mean = |name: &str| -> T
+            {
+                weights.iter().zip(group).fold(T::zero(), |total, (&w, (_, _, res))| total + w * res.get(name).unwrap())
+            };
+            let scatter = |name: &str| -> T
+            {
+                let average = mean(name);
+                weights.iter().zip(group).fold(T::zero(), |total, (&w, (_, _, res))| total + w * (res.get(name).unwrap() - average).powi(2))
+            };
+            let has = |name: &str| names.contains(&name);
+
+            let mut merged = MonteCarloResults::new();
+            for &name in &names
+            {
+                let value = match name
+                {
+                    name if name.ends_with("_err") =>
+                    {
+                        weights.iter().zip(group).fold(T::zero(), |total, (&w, (_, _, res))| total + (w * res.get(name).unwrap()).powi(2)).sqrt()
+                    }
+                    "specific_heat" if has("energy_density")  => mean(name) + num_spins * scatter("energy_density") / temp.powi(2),
+                    "susceptibility" if has("magnetisation")  => mean(name) + num_spins * scatter("magnetisation") / temp,
+                    name => mean(name),
+                };
+                merged.push(name, value);
+            }
+            merged.push(MEASUREMENTS_COLUMN, measurements);
+            temperatures.push(temp);
+            results.push(merged);
+        }
+
+        Ok(Self { file_name: String::new(), format: first.format, temperatures, results, metadata: merged_metadata(files) })
+    }
+}
+
+fn merged_metadata<T: Float>(files: &[ResultsFile<T>]) -> Vec<(String, String)>
+{
+    let mut metadata = Vec::new();
+    let elapsed_times: Option<Vec<f64>> = files.iter().map(ResultsFile::elapsed_time).collect();
+    if let Some(elapsed_times) = elapsed_times
+    {
+        metadata.push(("elapsed_time".to_string(), elapsed_times.iter().sum::<f64>().to_string()));
+    }
+    let merged_runs: usize = files.iter().map(|file| file.parse_metadata("merged_runs").unwrap_or(1)).sum();
+    metadata.push(("merged_runs".to_string(), merged_runs.to_string()));
+
+    let skipped = ["elapsed_time", "merged_runs", "measure_steps"];
+    for (key, _) in files.iter().flat_map(|file| &file.metadata)
+    {
+        if skipped.contains(&key.as_str()) || metadata.iter().any(|(merged, _)| merged == key)
+        {
+            continue;
+        }
+        let mut values: Vec<&str> = Vec::new();
+        for value in files.iter().filter_map(|file| file.metadata(key))
+        {
+            value.split(';').for_each(|value| if !values.contains(&value) { values.push(value) });
+        }
+        metadata.push((key.clone(), values.join(";")));
+    }
+    metadata
+}
@@ -0,0 +1,278 @@
+use num_traits::Float;
+use serde_json::{json, Map, Number, Value};
+use std::io::Write;
+use std::iter::zip;
+use std::path::Path;
+use std::str::FromStr;
+
+
+// Layout of the results file, every format holds one record per temperature ("temp" followed by the observables)
+// and the run parameters (elapsed_time, rows, cols, seed...):
+//      Legacy: "temp, energy_density, ..., elapsed_time: 46, seed: 42" header line, then ", " separated values
+//      Csv:    "# key: value" metadata lines, a header line, then "," separated values
+//              pd.read_csv(file_name, comment="#")
+//      Json:   {"metadata": {"elapsed_time": 46.2, ...}, "columns": ["temp", ...], "results": [{"temp": 2.0, ...}, ...]}
+//              non finite values are written as null
+//      Npy:    structured array of shape (temperatures,) with one "<f8" field per column.
+//              The .npy header cannot hold anything else: the run parameters are written next to it, as Json's
+//              "metadata" object, see metadata_file_name_for
+//              np.load(file_name)["magnetisation"]; json.load(open(metadata_file_name))
+//      Npz:    uncompressed archive of "results.npy" (as Npy) and "metadata.npy",
+//              a structured array of ("key", "value") unicode strings
+//              data = np.load(file_name); dict(data["metadata"].tolist())
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat
+{
+    #[default]
+    Legacy,
+    Csv,
+    Json,
+    Npy,
+    Npz,
+}
+
+impl OutputFormat
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            OutputFormat::Legacy => "legacy",
+            OutputFormat::Csv    => "csv",
+            OutputFormat::Json   => "json",
+            OutputFormat::Npy    => "npy",
+            OutputFormat::Npz    => "npz",
+        }
+    }
+    // None: the legacy format keeps the output file name as given
+    pub fn extension(&self) -> Option<&'static str>
+    {
+        match self
+        {
+            OutputFormat::Legacy => None,
+            other                => Some(other.name()),
+        }
+    }
+    // "dir/out_rows=8,cols=8.txt" => "dir/out_rows=8,cols=8.json"
+    pub fn file_name_for(&self, output_file: &str) -> String
+    {
+        match self.extension()
+        {
+            Some(extension) => Path::new(output_file).with_extension(extension).to_string_lossy().into_owned(),
+            None            => output_file.to_string(),
+        }
+    }
+    // run parameters of an Npy file: "dir/out_rows=8,cols=8.npy" => "dir/out_rows=8,cols=8_metadata.json"
+    pub fn metadata_file_name_for(npy_file: &str) -> String
+    {
+        let path = Path::new(npy_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        path.with_file_name(format!("{stem}_metadata.json")).to_string_lossy().into_owned()
+    }
+}
+
+impl FromStr for OutputFormat
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "legacy" | "txt" => Ok(OutputFormat::Legacy),
+            "csv"            => Ok(OutputFormat::Csv),
+            "json"           => Ok(OutputFormat::Json),
+            "npy"            => Ok(OutputFormat::Npy),
+            "npz"            => Ok(OutputFormat::Npz),
+            other => Err(format!("Unknown output format \"{other}\" (expected legacy, csv, json, npy or npz)")),
+        }
+    }
+}
+
+
+pub(super) fn write_csv<W: Write, T: Float + std::fmt::Display>(writer: &mut W, columns: &[&str], rows: &[Vec<T>], metadata: &[(String, String)]) -> std::io::Result<()>
+{
+    for (key, value) in metadata
+    {
+        writeln!(writer, "# {key}: {value}")?;
+    }
+    writeln!(writer, "{}", columns.join(","))?;
+    for row in rows
+    {
+        let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+        writeln!(writer, "{}", values.join(","))?;
+    }
+    Ok(())
+}
+
+// Metadata values are written as JSON booleans or numbers when they parse as such, as strings otherwise
+fn json_metadata_value(value: &str) -> Value
+{
+    if let Ok(boolean) = value.parse::<bool>()
+    {
+        return Value::Bool(boolean);
+    }
+    if let Ok(integer) = value.parse::<u64>()
+    {
+        return Value::from(integer);
+    }
+    match value.parse::<f64>().ok().and_then(Number::from_f64)
+    {
+        Some(number) => Value::Number(number),
+        None         => Value::String(value.to_string()),
+    }
+}
+
+fn json_metadata(metadata: &[(String, String)]) -> Map<String, Value>
+{
+    metadata.iter().map(|(key, value)| (key.clone(), json_metadata_value(value))).collect()
+}
+
+pub(super) fn write_json<W: Write, T: Float>(writer: &mut W, columns: &[&str], rows: &[Vec<T>], metadata: &[(String, String)]) -> std::io::Result<()>
+{
+    let metadata = json_metadata(metadata);
+    let records: Vec<Value> = rows.iter().map(|row|
+    {
+        let record: Map<String, Value> = zip(columns, row).map(|(&name, value)|
+        {
+            let value = value.to_f64().and_then(Number::from_f64).map_or(Value::Null, Value::Number);
+            (name.to_string(), value)
+        }).collect();
+        Value::Object(record)
+    }).collect();
+
+    let document = json!({ "metadata": metadata, "columns": columns, "results": records });
+    serde_json::to_writer_pretty(&mut *writer, &document)?;
+    writeln!(writer)
+}
+
+// the metadata file of an Npy file: the "metadata" object of write_json alone
+pub(super) fn write_json_metadata<W: Write>(writer: &mut W, metadata: &[(String, String)]) -> std::io::Result<()>
+{
+    serde_json::to_writer_pretty(&mut *writer, &json_metadata(metadata))?;
+    writeln!(writer)
+}
+
+
+pub(super) const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+// Python literal of a field name
+fn npy_quote(name: &str) -> String
+{
+    format!("'{}'", name.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// .npy version 1.0 file of a 1D array: the header is padded with spaces so that the data is 64 byte aligned
+fn npy_bytes(descr: &str, length: usize, data: &[u8]) -> Vec<u8>
+{
+    let mut header  = format!("{{'descr': {descr}, 'fortran_order': False, 'shape': ({length},), }}");
+    let unpadded    = NPY_MAGIC.len() + 2 + 2 + header.len() + 1; // magic, version, header length, header, '\n'
+    header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(NPY_MAGIC.len() + 4 + header.len() + data.len());
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+pub(super) fn results_npy<T: Float>(columns: &[&str], rows: &[Vec<T>]) -> Vec<u8>
+{
+    let fields: Vec<String> = columns.iter().map(|name| format!("({}, '<f8')", npy_quote(name))).collect();
+    let descr               = format!("[{}]", fields.join(", "));
+    let data: Vec<u8>       = rows.iter().flatten()
+        .flat_map(|value| value.to_f64().unwrap_or(f64::NAN).to_le_bytes())
+        .collect();
+    npy_bytes(&descr, rows.len(), &data)
+}
+
+// ("key", "<Un"), ("value", "<Um"): fixed length UTF-32 strings, zero padded
+pub(super) fn metadata_npy(metadata: &[(String, String)]) -> Vec<u8>
+{
+    let key_length   = metadata.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0).max(1);
+    let value_length = metadata.iter().map(|(_, value)| value.chars().count()).max().unwrap_or(0).max(1);
+    let descr        = format!("[('key', '<U{key_length}'), ('value', '<U{value_length}')]");
+
+    let utf32 = |text: &str, length: usize| -> Vec<u8>
+    {
+        text.chars().map(|c| c as u32).chain(std::iter::repeat(0))
+            .take(length)
+            .flat_map(u32::to_le_bytes)
+            .collect()
+    };
+    let data: Vec<u8> = metadata.iter().flat_map(|(key, value)|
+    {
+        let mut record = utf32(key, key_length);
+        record.extend(utf32(value, value_length));
+        record
+    }).collect();
+    npy_bytes(&descr, metadata.len(), &data)
+}
+
+
+// CRC-32 (IEEE) as required by zip archives, bit by bit: the archives are small
+fn crc32(data: &[u8]) -> u32
+{
+    let mut crc = !0_u32;
+    for &byte in data
+    {
+        crc ^= byte as u32;
+        for _ in 0..8
+        {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// Uncompressed ("stored") zip archive, as written by np.savez
+pub(super) fn write_zip<W: Write>(writer: &mut W, files: &[(&str, Vec<u8>)]) -> std::io::Result<()>
+{
+    const DOS_DATE: u16 = (1 << 5) | 1; // 1980-01-01
+    let mut offset            = 0_u32;
+    let mut central_directory = Vec::new();
+
+    for (name, data) in files
+    {
+        let crc  = crc32(data);
+        let size = u32::try_from(data.len()).map_err(|_| std::io::Error::other("npz member larger than 4GB"))?;
+
+        // fields shared by the local header & the central directory entry: from "version needed" to "extra field length"
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20_u16.to_le_bytes());       // version needed
+        common.extend_from_slice(&0_u16.to_le_bytes());        // flags
+        common.extend_from_slice(&0_u16.to_le_bytes());        // method: stored
+        common.extend_from_slice(&0_u16.to_le_bytes());        // time
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&size.to_le_bytes());         // compressed size
+        common.extend_from_slice(&size.to_le_bytes());         // uncompressed size
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0_u16.to_le_bytes());        // extra field length
+
+        writer.write_all(&0x0403_4B50_u32.to_le_bytes())?;
+        writer.write_all(&common)?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(data)?;
+
+        central_directory.extend_from_slice(&0x0201_4B50_u32.to_le_bytes());
+        central_directory.extend_from_slice(&20_u16.to_le_bytes()); // version made by
+        central_directory.extend_from_slice(&common);
+        central_directory.extend_from_slice(&[0; 10]);              // comment length, disk number, internal & external attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes()); // of the local header
+        central_directory.extend_from_slice(name.as_bytes());
+
+        offset += (30 + name.len()) as u32 + size;
+    }
+
+    writer.write_all(&central_directory)?;
+    writer.write_all(&0x0605_4B50_u32.to_le_bytes())?;
+    writer.write_all(&[0; 4])?;                                         // disk numbers
+    writer.write_all(&(files.len() as u16).to_le_bytes())?;
+    writer.write_all(&(files.len() as u16).to_le_bytes())?;
+    writer.write_all(&(central_directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&0_u16.to_le_bytes())                              // comment length
+}
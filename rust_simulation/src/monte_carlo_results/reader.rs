@@ -0,0 +1,379 @@
+use num_traits::Float;
+use serde_json::Value;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::output_format::NPY_MAGIC;
+use super::{MonteCarloResults, OutputFormat};
+
+
+// Contents of a results file written by MonteCarloResults::write_as, in any OutputFormat
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultsFile<T> where T: Float
+{
+    pub file_name: String,
+    pub format: OutputFormat,
+    pub temperatures: Vec<T>,
+    pub results: Vec<MonteCarloResults<T>>,
+    pub metadata: Vec<(String, String)>, // elapsed_time & run parameters, in file order
+}
+
+impl<T> ResultsFile<T> where T: Float
+{
+    pub fn metadata(&self, key: &str) -> Option<&str>
+    {
+        self.metadata.iter().find(|(name, _)| name == key).map(|(_, value)| value.as_str())
+    }
+    pub fn parse_metadata<V: FromStr>(&self, key: &str) -> Option<V>
+    {
+        self.metadata(key)?.parse().ok()
+    }
+    // in seconds (whole seconds in legacy files)
+    pub fn elapsed_time(&self) -> Option<f64>
+    {
+        self.parse_metadata("elapsed_time")
+    }
+    // from the rows & cols run parameters, or else from a "rows=R,cols=C" file name (older legacy files)
+    pub fn shape(&self) -> Option<(usize, usize)>
+    {
+        if let (Some(rows), Some(cols)) = (self.parse_metadata("rows"), self.parse_metadata("cols"))
+        {
+            return Some((rows, cols));
+        }
+        let stem = Path::new(&self.file_name).file_stem()?.to_str()?;
+        let size = |key: &str| -> Option<usize>
+        {
+            let start  = stem.find(key)? + key.len();
+            let digits = stem[start..].chars().take_while(char::is_ascii_digit).collect::<String>();
+            digits.parse().ok()
+        };
+        Some((size("rows=")?, size("cols=")?))
+    }
+    // values of one result over the temperatures
+    pub fn column(&self, name: &str) -> Option<Vec<T>>
+    {
+        self.results.iter().map(|res| res.get(name)).collect()
+    }
+}
+
+impl<T> ResultsFile<T> where T: Float + std::fmt::Display
+{
+    // metadata included, e.g. to convert a legacy file or to save merged runs
+    pub fn write_as(&self, format: OutputFormat, file_name: &str) -> std::io::Result<()>
+    {
+        MonteCarloResults::write_with_metadata(format, file_name, &self.temperatures, &self.results, &self.metadata)
+    }
+}
+
+
+fn invalid_data<E: std::fmt::Display>(file_name: &str, err: E) -> std::io::Error
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{file_name}: {err}"))
+}
+
+fn parse_value<T: Float>(value: &str) -> Result<T, String>
+{
+    let value = value.trim();
+    value.parse::<f64>().ok().and_then(T::from).ok_or_else(|| format!("could not parse \"{value}\" as a number"))
+}
+
+// The format is recognised from the content, not from the extension
+pub(super) fn read<T: Float>(file_name: &str) -> std::io::Result<ResultsFile<T>>
+{
+    let bytes = std::fs::read(file_name)?;
+    let (format, columns, rows, metadata) = if bytes.starts_with(b"PK\x03\x04")
+    {
+        read_npz(&bytes)
+    }
+    else if bytes.starts_with(NPY_MAGIC)
+    {
+        parse_npy(&bytes).and_then(|array| results_from_npy(&array)).and_then(|(columns, rows)|
+        {
+            // the run parameters are in the metadata file, if it was kept next to the results
+            let metadata = match std::fs::read_to_string(OutputFormat::metadata_file_name_for(file_name))
+            {
+                Ok(text) => metadata_from_json(&serde_json::from_str(&text).map_err(|err| format!("metadata file: {err}"))?),
+                Err(_)   => Vec::new(),
+            };
+            Ok((OutputFormat::Npy, columns, rows, metadata))
+        })
+    }
+    else
+    {
+        let text = String::from_utf8(bytes).map_err(|err| invalid_data(file_name, err))?;
+        match text.trim_start().chars().next()
+        {
+            Some('{') => read_json(&text),
+            Some('#') => read_text(&text, OutputFormat::Csv),
+            _         => read_text(&text, OutputFormat::Legacy),
+        }
+    }.map_err(|err| invalid_data(file_name, err))?;
+
+    let temp_column = columns.iter().position(|name| name == "temp").ok_or_else(|| invalid_data(file_name, "no \"temp\" column"))?;
+    let temperatures = rows.iter().map(|row: &Vec<f64>| T::from(row[temp_column]).unwrap()).collect();
+    let results      = rows.iter().map(|row|
+    {
+        columns.iter().zip(row).enumerate()
+            .filter(|&(column, _)| column != temp_column)
+            .map(|(_, (name, &value))| (name.clone(), T::from(value).unwrap()))
+            .collect()
+    }).collect();
+
+    Ok(ResultsFile { file_name: file_name.to_string(), format, temperatures, results, metadata })
+}
+
+
+type Table = (OutputFormat, Vec<String>, Vec<Vec<f64>>, Vec<(String, String)>);
+
+// Legacy: "temp, energy_density, ..., elapsed_time: 46, seed: 42" then ", " separated values
+// Csv:    "# key: value" lines, "temp,energy_density,..." then "," separated values
+fn read_text(text: &str, format: OutputFormat) -> Result<Table, String>
+{
+    let mut metadata = Vec::new();
+    let mut lines    = text.lines().filter(|line| !line.trim().is_empty());
+    let mut header   = lines.next().ok_or("empty file")?;
+    while let Some(comment) = header.strip_prefix('#')
+    {
+        if let Some((key, value)) = comment.split_once(':')
+        {
+            metadata.push((key.trim().to_string(), value.trim().to_string()));
+        }
+        header = lines.next().ok_or("no header line")?;
+    }
+
+    let mut columns = Vec::new();
+    for field in header.split(',').map(str::trim)
+    {
+        match field.split_once(':')
+        {
+            Some((key, value)) => metadata.push((key.trim().to_string(), value.trim().to_string())),
+            None               => columns.push(field.to_string()),
+        }
+    }
+
+    let rows = lines.enumerate().map(|(line_number, line)|
+    {
+        let row = line.split(',').map(parse_value).collect::<Result<Vec<f64>, _>>()?;
+        match row.len() == columns.len()
+        {
+            true  => Ok(row),
+            false => Err(format!("line {}: {} values for {} columns", line_number + 2, row.len(), columns.len())),
+        }
+    }).collect::<Result<_, String>>()?;
+
+    Ok((format, columns, rows, metadata))
+}
+
+// {"elapsed_time": 46.2, "seed": 42, ...}, the values are read back as they were written
+fn metadata_from_json(object: &Value) -> Vec<(String, String)>
+{
+    match object
+    {
+        Value::Object(map) => map.iter().map(|(key, value)|
+        {
+            let value = match value
+            {
+                Value::String(text) => text.clone(),
+                other               => other.to_string(),
+            };
+            (key.clone(), value)
+        }).collect(),
+        _ => Vec::new(),
+    }
+}
+
+// {"metadata": {...}, "columns": [...], "results": [{"temp": 2.0, ...}, ...]}, null values are read as NaN
+fn read_json(text: &str) -> Result<Table, String>
+{
+    let document: Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+
+    let metadata = document.get("metadata").map(metadata_from_json).unwrap_or_default();
+    let columns: Vec<String> = document.get("columns").and_then(Value::as_array).ok_or("no \"columns\" list")?
+        .iter()
+        .map(|name| name.as_str().map(str::to_string).ok_or("column names should be strings"))
+        .collect::<Result<_, _>>()?;
+    let rows = document.get("results").and_then(Value::as_array).ok_or("no \"results\" list")?
+        .iter()
+        .map(|record| columns.iter().map(|name| match record.get(name)
+        {
+            Some(Value::Null) => Ok(f64::NAN),
+            Some(value)       => value.as_f64().ok_or(format!("\"{name}\" should be a number")),
+            None              => Err(format!("missing \"{name}\" in a record")),
+        }).collect::<Result<Vec<f64>, String>>())
+        .collect::<Result<_, _>>()?;
+
+    Ok((OutputFormat::Json, columns, rows, metadata))
+}
+
+
+// 1D structured array: (field name, dtype) & the raw data
+struct NpyArray<'a>
+{
+    fields: Vec<(String, String)>,
+    length: usize,
+    data: &'a [u8],
+}
+
+fn parse_npy(bytes: &[u8]) -> Result<NpyArray<'_>, String>
+{
+    let (header_start, header_length) = match bytes.get(6..12)
+    {
+        Some(&[1, _, a, b, ..])   => (10, u16::from_le_bytes([a, b]) as usize),
+        Some(&[2 | 3, _, a, b, c, d]) => (12, u32::from_le_bytes([a, b, c, d]) as usize),
+        _ => return Err("unsupported .npy version".to_string()),
+    };
+    let header = bytes.get(header_start..header_start + header_length).ok_or("truncated .npy header")?;
+    let header = std::str::from_utf8(header).map_err(|err| err.to_string())?;
+
+    if header.contains("'fortran_order': True")
+    {
+        return Err("fortran ordered arrays are not supported".to_string());
+    }
+    let descr = header.split_once("'descr':").ok_or("no descr in the .npy header")?.1.trim_start();
+    let descr = descr.strip_prefix('[').ok_or("only structured arrays are supported")?;
+
+    // [('name', '<f8'), ...]: every field is a pair of python string literals
+    let mut fields = Vec::new();
+    let mut rest   = descr;
+    while let Some(start) = rest.find(['(', ']'])
+    {
+        if rest[start..].starts_with(']')
+        {
+            break;
+        }
+        let (name, after_name)   = python_string(&rest[start + 1..])?;
+        let after_comma          = after_name.trim_start().strip_prefix(',').ok_or("malformed descr")?;
+        let (dtype, after_dtype) = python_string(after_comma)?;
+        fields.push((name, dtype));
+        rest = after_dtype;
+    }
+
+    let shape  = header.split_once("'shape':").ok_or("no shape in the .npy header")?.1;
+    let shape  = shape.trim_start().strip_prefix('(').ok_or("malformed shape")?;
+    let length = shape.split([',', ')']).next().unwrap_or("").trim().parse().map_err(|_| "only 1D arrays are supported")?;
+
+    Ok(NpyArray { fields, length, data: &bytes[header_start + header_length..] })
+}
+
+// reads a '...' or "..." literal at the start of `text` (after whitespace), returns it & what follows
+fn python_string(text: &str) -> Result<(String, &str), String>
+{
+    let text  = text.trim_start();
+    let quote = text.chars().next().filter(|c| *c == '\'' || *c == '"').ok_or("expected a string in descr")?;
+
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((index, c)) = chars.next()
+    {
+        match c
+        {
+            '\\' => value.extend(chars.next().map(|(_, escaped)| escaped)),
+            c if c == quote => return Ok((value, &text[index + 1..])),
+            c    => value.push(c),
+        }
+    }
+    Err("unterminated string in descr".to_string())
+}
+
+fn results_from_npy(array: &NpyArray) -> Result<(Vec<String>, Vec<Vec<f64>>), String>
+{
+    if let Some((name, dtype)) = array.fields.iter().find(|(_, dtype)| dtype != "<f8")
+    {
+        return Err(format!("field \"{name}\" has dtype {dtype}, expected <f8"));
+    }
+    let width = array.fields.len();
+    if array.data.len() < array.length * width * 8
+    {
+        return Err("truncated .npy data".to_string());
+    }
+    let values: Vec<f64> = array.data.chunks_exact(8).take(array.length * width)
+        .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+        .collect();
+    let columns = array.fields.iter().map(|(name, _)| name.clone()).collect();
+    let rows    = values.chunks(width.max(1)).map(<[f64]>::to_vec).collect();
+    Ok((columns, rows))
+}
+
+// ("key", "<Un"), ("value", "<Um") records
+fn metadata_from_npy(array: &NpyArray) -> Result<Vec<(String, String)>, String>
+{
+    let lengths = array.fields.iter().map(|(_, dtype)|
+    {
+        dtype.strip_prefix("<U").and_then(|length| length.parse::<usize>().ok()).ok_or(format!("metadata dtype {dtype}, expected <U"))
+    }).collect::<Result<Vec<usize>, _>>()?;
+    let &[key_length, value_length] = lengths.as_slice() else
+    {
+        return Err("metadata should have a key & a value field".to_string());
+    };
+
+    let utf32 = |bytes: &[u8]| -> String
+    {
+        bytes.chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .take_while(|&c| c != 0)
+            .filter_map(char::from_u32)
+            .collect()
+    };
+    let record = 4 * (key_length + value_length);
+    if array.data.len() < array.length * record
+    {
+        return Err("truncated metadata".to_string());
+    }
+    Ok(array.data.chunks_exact(record).take(array.length)
+        .map(|record| (utf32(&record[..4 * key_length]), utf32(&record[4 * key_length..])))
+        .collect())
+}
+
+
+// Members of a zip archive, found through its central directory: only stored (uncompressed) members are supported
+fn zip_members(bytes: &[u8]) -> Result<Vec<(String, &[u8])>, String>
+{
+    let u16_at = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as usize).ok_or("truncated zip archive");
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize).ok_or("truncated zip archive");
+
+    let end = (0..bytes.len().saturating_sub(21)).rev()
+        .find(|&at| bytes[at..].starts_with(&[0x50, 0x4B, 0x05, 0x06]))
+        .ok_or("no end of central directory")?;
+    let num_entries = u16_at(end + 10)?;
+    let mut entry   = u32_at(end + 16)?;
+
+    let mut members = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries
+    {
+        if u32_at(entry)? != 0x0201_4B50
+        {
+            return Err("corrupted central directory".to_string());
+        }
+        let method      = u16_at(entry + 10)?;
+        let size        = u32_at(entry + 20)?;
+        let name_length = u16_at(entry + 28)?;
+        let skipped     = u16_at(entry + 30)? + u16_at(entry + 32)?; // extra field & comment
+        let local       = u32_at(entry + 42)?;
+        let name        = bytes.get(entry + 46..entry + 46 + name_length).ok_or("truncated zip archive")?;
+        let name        = String::from_utf8_lossy(name).into_owned();
+        if method != 0
+        {
+            return Err(format!("{name} is compressed, only np.savez (not savez_compressed) archives are supported"));
+        }
+
+        let data_start = local + 30 + u16_at(local + 26)? + u16_at(local + 28)?;
+        let data       = bytes.get(data_start..data_start + size).ok_or("truncated zip member")?;
+        members.push((name, data));
+        entry += 46 + name_length + skipped;
+    }
+    Ok(members)
+}
+
+fn read_npz(bytes: &[u8]) -> Result<Table, String>
+{
+    let members = zip_members(bytes)?;
+    let member  = |name: &str| members.iter().find(|(member, _)| member == name).map(|&(_, data)| data);
+
+    let (columns, rows) = results_from_npy(&parse_npy(member("results.npy").ok_or("no results.npy in the archive")?)?)?;
+    let metadata        = match member("metadata.npy")
+    {
+        Some(data) => metadata_from_npy(&parse_npy(data)?)?,
+        None       => Vec::new(),
+    };
+    Ok((OutputFormat::Npz, columns, rows, metadata))
+}
@@ -0,0 +1,406 @@
+use num::complex::Complex64;
+use std::f64::consts::PI;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::statistics::{Binning, Histogram, RunningMoments};
+use crate::swendsen_wang_algorithm::{ClusterMap, SpinLattice};
+
+
+// What an observable sees after every measurement sweep.
+// energy & spin_sum belong to the configuration the clusters were built on (before the flip),
+// spins & the Fourier amplitudes to the configuration after the flip: both are equilibrium samples,
+// and flipping clusters does not change them, so the clusters are those of either configuration.
+pub struct Measurement<'a>
+{
+    pub temperature: f64,
+    pub spins: &'a dyn SpinLattice,
+    pub energy: f64,              // total energy
+    pub spin_sum: f64,            // signed sum of the spins
+    pub spin_q0: f64,             // sigma(q=0) / sqrt(N), zero unless a Fourier transform was requested
+    pub spin_qx: Complex64,       // sigma(q=2pi/Lx) / sqrt(N), idem
+    pub has_fourier_transform: bool, // false between the measurements of SimulationBuilder::fourier_interval
+    pub clusters: Option<&'a ClusterMap>, // only if an observable needs_clusters
+}
+
+// Passed to the observables when the results are computed
+pub struct Summary
+{
+    pub temperature: f64,
+    pub rows: usize,
+    pub cols: usize,
+    pub num_measurements: usize,
+}
+
+impl Summary
+{
+    #[inline]
+    pub fn num_spins(&self) -> f64
+    {
+        (self.rows*self.cols) as f64
+    }
+}
+
+// A quantity accumulated over the measurement sweeps of one temperature.
+// Every temperature gets its own instance: see SimulationBuilder::observable.
+pub trait Observable: Send
+{
+    fn measure(&mut self, measurement: &Measurement);
+    // named scalar results, written as columns of the output file in this order
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>;
+
+    fn needs_clusters(&self) -> bool
+    {
+        false
+    }
+    fn needs_fourier_transform(&self) -> bool
+    {
+        false
+    }
+
+    // Accumulated state, stored in checkpoints. load_state reads its values from the front of `state`
+    // and returns how many it used (None if `state` is too short).
+    // The defaults store nothing: an observable accumulating over the sweeps needs both for resumed runs to be exact.
+    fn save_state(&self) -> Vec<f64>
+    {
+        Vec::new()
+    }
+    fn load_state(&mut self, _state: &[f64]) -> Option<usize>
+    {
+        Some(0)
+    }
+
+    // Output that does not fit in scalar results (distributions...), written next to `output_file` once
+    // the temperature is done. Only called if the simulation has an output file.
+    fn write_files(&self, _summary: &Summary, _output_file: &str) -> std::io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+
+// Energy & magnetisation moments:
+//      energy_density, magnetisation (<|m|>), specific_heat, susceptibility
+// The fluctuations are accumulated with Welford's scheme: on large lattices <E²> - <E>² is the difference of two nearly
+// equal numbers, computed from raw sums it would lose most of its significant digits (catastrophic cancellation).
+#[derive(Debug, Default, Clone)]
+pub struct Thermodynamics
+{
+    energy: RunningMoments,
+    abs_spin_sum: RunningMoments,
+}
+
+impl Observable for Thermodynamics
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        self.energy.push(measurement.energy);
+        self.abs_spin_sum.push(measurement.spin_sum.abs());
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        let num_spins = summary.num_spins();
+        let temp      = summary.temperature;
+
+        let specific_heat  = self.energy.variance() / (temp.powi(2) * num_spins);
+        let energy_density = self.energy.mean() / num_spins;
+        let magnetisation  = self.abs_spin_sum.mean() / num_spins;
+        let susceptibility = self.abs_spin_sum.variance() / (temp * num_spins);
+        vec![
+            ("energy_density".to_string(), energy_density),
+            ("magnetisation".to_string(),  magnetisation),
+            ("specific_heat".to_string(),  specific_heat),
+            ("susceptibility".to_string(), susceptibility),
+        ]
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        [self.energy.to_array(), self.abs_spin_sum.to_array()].concat()
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[e0, e1, e2, m0, m1, m2, ..] = state else
+        {
+            return None;
+        };
+        self.energy       = RunningMoments::from_array([e0, e1, e2]);
+        self.abs_spin_sum = RunningMoments::from_array([m0, m1, m2]);
+        Some(6)
+    }
+}
+
+
+// Signed magnetisation & the susceptibility without subtraction:
+//      signed_magnetisation:  <m>, zero on average in a finite lattice (both signs are equally likely)
+//      susceptibility_m2:     chi = N <m²> / T, the right estimator above Tc, while Thermodynamics' susceptibility is
+//                             chi' = N (<m²> - <|m|>²) / T, the one to use below Tc
+//      sign_flip_rate:        fraction of consecutive measurements whose magnetisation has opposite signs
+#[derive(Debug, Default, Clone)]
+pub struct Magnetisation
+{
+    spin_sum: RunningMoments,
+    sign_flips: f64,
+    last_sign: f64,   // sign of the last non zero magnetisation, 0 before
+}
+
+impl Observable for Magnetisation
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        let spin_sum = measurement.spin_sum;
+        self.spin_sum.push(spin_sum);
+        if spin_sum != 0_f64
+        {
+            let sign = spin_sum.signum();
+            if sign == -self.last_sign
+            {
+                self.sign_flips += 1_f64;
+            }
+            self.last_sign = sign;
+        }
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        let num_spins = summary.num_spins();
+        let temp      = summary.temperature;
+
+        let signed_magnetisation = self.spin_sum.mean() / num_spins;
+        let susceptibility_m2    = self.spin_sum.mean_sqr() / (temp * num_spins);
+        let sign_flip_rate       = self.sign_flips / (self.spin_sum.count().max(2) - 1) as f64;
+        vec![
+            ("signed_magnetisation".to_string(), signed_magnetisation),
+            ("susceptibility_m2".to_string(),    susceptibility_m2),
+            ("sign_flip_rate".to_string(),       sign_flip_rate),
+        ]
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        [&self.spin_sum.to_array()[..], &[self.sign_flips, self.last_sign]].concat()
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[count, mean, m2, sign_flips, last_sign, ..] = state else
+        {
+            return None;
+        };
+        *self = Self { spin_sum: RunningMoments::from_array([count, mean, m2]), sign_flips, last_sign };
+        Some(5)
+    }
+}
+
+
+// Second moment correlation length from the structure factor S(q) = <|sigma_q|²>:
+//      xi = sqrt(S(0)/S(qx) - 1) / qx, with qx = 2pi/Lx
+// zero if the Fourier amplitudes were not measured (SimulationBuilder::measure_structure_factor)
+// averaged over the measurements with a Fourier transform only (SimulationBuilder::fourier_interval)
+#[derive(Debug, Default, Clone)]
+pub struct StructureFactor
+{
+    re_spin_q0_sqr: f64, //  <Re[sigma_q0]²>
+    re_spin_qx_sqr: f64, //  <Re[sigma_qx]²>
+    im_spin_qx_sqr: f64, //  <Im[sigma_qx]²>
+    num_transforms: f64,
+}
+
+impl Observable for StructureFactor
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        if !measurement.has_fourier_transform
+        {
+            return;
+        }
+        let (spin_q0, spin_qx) = (measurement.spin_q0, measurement.spin_qx);
+        self.num_transforms += 1_f64;
+        self.re_spin_q0_sqr += spin_q0*spin_q0;
+        self.re_spin_qx_sqr += spin_qx.re*spin_qx.re;
+        self.im_spin_qx_sqr += spin_qx.im*spin_qx.im;
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        let num_transforms = self.num_transforms.max(1_f64);
+        let struct_fact_q0 = self.re_spin_q0_sqr/num_transforms;                           //S(q0) =  <Re[sigma_q0]²>
+        let struct_fact_qx = (self.re_spin_qx_sqr + self.im_spin_qx_sqr)/num_transforms;   //S(qx) =  <Re[sigma_qx]²> + <Im[sigma_qx]²>
+
+        let qx              = 2_f64 * PI / summary.cols as f64;
+        let mut corr_length = 0_f64;
+        if struct_fact_q0 != 0_f64 && struct_fact_qx != 0_f64
+        {
+            let sf_ratio = (struct_fact_q0/struct_fact_qx - 1_f64).abs().sqrt();
+            corr_length  = sf_ratio / qx;
+        }
+        vec![("correlation length".to_string(), corr_length)]
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        vec![self.re_spin_q0_sqr, self.re_spin_qx_sqr, self.im_spin_qx_sqr, self.num_transforms]
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[re_spin_q0_sqr, re_spin_qx_sqr, im_spin_qx_sqr, num_transforms, ..] = state else
+        {
+            return None;
+        };
+        *self = Self { re_spin_q0_sqr, re_spin_qx_sqr, im_spin_qx_sqr, num_transforms };
+        Some(4)
+    }
+}
+
+
+// Statistical errors of the Thermodynamics results, from the jackknife over bins of sweeps (see Binning):
+//      energy_density_err, magnetisation_err, specific_heat_err, susceptibility_err
+// Energies & |M| are binned relative to their first measured value, so that E² stays small on large lattices.
+// Meaningful once the bins are much longer than the autocorrelation time, i.e. with many more sweeps than MAX_BINS.
+#[derive(Debug, Default, Clone)]
+pub struct ErrorEstimates
+{
+    shift: Option<[f64; 2]>,  // first E & |M|
+    bins: Binning<4>,         // E, E², |M|, |M|² (shifted)
+}
+
+impl ErrorEstimates
+{
+    // results which get an error column "{name}_err"
+    pub const OBSERVABLES: [&str; 4] = ["energy_density", "magnetisation", "specific_heat", "susceptibility"];
+}
+
+impl Observable for ErrorEstimates
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        let (energy, abs_spin_sum) = (measurement.energy, measurement.spin_sum.abs());
+        let [energy_shift, spin_shift] = *self.shift.get_or_insert([energy, abs_spin_sum]);
+        let (energy, abs_spin_sum) = (energy - energy_shift, abs_spin_sum - spin_shift);
+        self.bins.push([energy, energy * energy, abs_spin_sum, abs_spin_sum * abs_spin_sum]);
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        let num_spins = summary.num_spins();
+        let temp      = summary.temperature;
+        let errors    = [
+            self.bins.jackknife_error(|means| means[0] / num_spins),
+            self.bins.jackknife_error(|means| means[2] / num_spins),
+            self.bins.jackknife_error(|means| (means[1] - means[0].powi(2)) / (temp.powi(2) * num_spins)),
+            self.bins.jackknife_error(|means| (means[3] - means[2].powi(2)) / (temp * num_spins)),
+        ];
+        Self::OBSERVABLES.iter().zip(errors).map(|(name, error)| (format!("{name}_err"), error)).collect()
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        let shift = self.shift.unwrap_or([f64::NAN; 2]);
+        shift.into_iter().chain(self.bins.to_vec()).collect()
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[energy_shift, spin_shift, ..] = state else
+        {
+            return None;
+        };
+        self.shift = (!energy_shift.is_nan()).then_some([energy_shift, spin_shift]);
+        self.bins.load(&state[2..]).map(|used| used + 2)
+    }
+}
+
+
+// Distributions P(m) of the (signed) magnetisation per spin over [-1, 1] and P(E) of the energy per spin over [-2, 2],
+// written to one file per temperature & quantity, see Histograms::file_name_for. No scalar results.
+// m takes N+1 values 2/N apart & E/N steps of 4/N: more bins than N/2 (m) or N (E) leave empty bins in between.
+#[derive(Debug, Clone)]
+pub struct Histograms
+{
+    magnetisation: Option<Histogram>,
+    energy: Option<Histogram>,
+}
+
+impl Histograms
+{
+    // 0 bins: that histogram is not measured
+    pub fn new(magnetisation_bins: usize, energy_bins: usize) -> Self
+    {
+        Self
+        {
+            magnetisation: (magnetisation_bins > 0).then(|| Histogram::new(-1_f64, 1_f64, magnetisation_bins)),
+            energy: (energy_bins > 0).then(|| Histogram::new(-2_f64, 2_f64, energy_bins)),
+        }
+    }
+    #[inline]
+    pub fn magnetisation(&self) -> Option<&Histogram>
+    {
+        self.magnetisation.as_ref()
+    }
+    #[inline]
+    pub fn energy(&self) -> Option<&Histogram>
+    {
+        self.energy.as_ref()
+    }
+    // "dir/out_rows=8,cols=8.txt" at T=2.25 => "dir/out_rows=8,cols=8_T=2.25_magnetisation_histogram.csv"
+    pub fn file_name_for(output_file: &str, temp: f64, quantity: &str) -> String
+    {
+        let path = Path::new(output_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        path.with_file_name(format!("{stem}_T={temp}_{quantity}_histogram.csv")).to_string_lossy().into_owned()
+    }
+
+    //      # temperature: 2.25
+    //      # measurements: 10000
+    //      # outside: 0
+    //      low,high,count,density
+    fn write_histogram(file_name: &str, histogram: &Histogram, summary: &Summary) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(std::fs::File::create(file_name)?);
+        writeln!(&mut file, "# temperature: {}", summary.temperature)?;
+        writeln!(&mut file, "# measurements: {}", histogram.total())?;
+        writeln!(&mut file, "# outside: {}", histogram.outside())?;
+        writeln!(&mut file, "low,high,count,density")?;
+        for (((low, high), count), density) in histogram.bin_edges().zip(histogram.counts()).zip(histogram.densities())
+        {
+            writeln!(&mut file, "{low},{high},{count},{density}")?;
+        }
+        file.flush()
+    }
+}
+
+impl Observable for Histograms
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        let (rows, cols) = measurement.spins.shape();
+        let num_spins    = (rows * cols) as f64;
+        if let Some(histogram) = self.magnetisation.as_mut()
+        {
+            histogram.push(measurement.spin_sum / num_spins);
+        }
+        if let Some(histogram) = self.energy.as_mut()
+        {
+            histogram.push(measurement.energy / num_spins);
+        }
+    }
+    fn results(&self, _summary: &Summary) -> Vec<(String, f64)>
+    {
+        Vec::new()
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        self.magnetisation.iter().chain(&self.energy).flat_map(Histogram::to_vec).collect()
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let mut used = 0;
+        for histogram in self.magnetisation.iter_mut().chain(self.energy.as_mut())
+        {
+            used += histogram.load(&state[used..])?;
+        }
+        Some(used)
+    }
+    fn write_files(&self, summary: &Summary, output_file: &str) -> std::io::Result<()>
+    {
+        for (quantity, histogram) in [("magnetisation", &self.magnetisation), ("energy", &self.energy)]
+        {
+            if let Some(histogram) = histogram
+            {
+                Self::write_histogram(&Self::file_name_for(output_file, summary.temperature, quantity), histogram, summary)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+// Deterministic random streams:
+// every (temperature, replica) pair gets its own seed derived from a single master seed,
+// so that results only depend on the master seed and not on the number of threads or the scheduling order.
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// SplitMix64 finaliser (Steele, Lea & Flood 2014): a bijective mixer with good avalanche properties
+#[inline(always)]
+pub(crate) fn splitmix64(mut z: u64) -> u64
+{
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub fn derive_seed(master_seed: u64, temperature_index: usize, replica: usize) -> u64
+{
+    let stream = splitmix64(master_seed.wrapping_add(GOLDEN_GAMMA));
+    let stream = splitmix64(stream ^ (temperature_index as u64).wrapping_mul(GOLDEN_GAMMA));
+    splitmix64(stream ^ (replica as u64).wrapping_add(1).wrapping_mul(GOLDEN_GAMMA.rotate_left(32)))
+}
+
+
+// Random generators available to the driver, see RngKind::from_str for their names in the parameter file.
+// Cluster algorithms are sensitive to correlations in the random numbers (Ferrenberg, Landau & Wong 1992),
+// so being able to swap generators is a cheap sanity check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RngKind
+{
+    #[default]
+    Small,           // Xoshiro256PlusPlus, the generator of rand::rngs::SmallRng
+    ChaCha,          // rand_chacha::ChaCha20Rng
+    MersenneTwister, // Mt64: MT19937-64
+    Pcg,             // rand_pcg::Pcg64
+}
+
+impl RngKind
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            RngKind::Small           => "small",
+            RngKind::ChaCha          => "chacha",
+            RngKind::MersenneTwister => "mt64",
+            RngKind::Pcg             => "pcg",
+        }
+    }
+}
+
+impl std::str::FromStr for RngKind
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "small" | "smallrng"       => Ok(RngKind::Small),
+            "chacha" | "chacha20"      => Ok(RngKind::ChaCha),
+            "mt64" | "mt19937_64" | "mersenne_twister" => Ok(RngKind::MersenneTwister),
+            "pcg" | "pcg64"            => Ok(RngKind::Pcg),
+            other => Err(format!("Unknown random generator \"{other}\" (expected small, chacha, mt64 or pcg)")),
+        }
+    }
+}
+
+
+// Generators whose full state can be saved in a checkpoint: the generator restored by from_state continues
+// the very same stream. None if `state` was not saved by the same kind of generator.
+pub trait RngState: Sized
+{
+    fn state(&self) -> Vec<u8>;
+    fn from_state(state: &[u8]) -> Option<Self>;
+}
+
+fn words_to_bytes(words: &[u64]) -> Vec<u8>
+{
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+fn bytes_to_words(bytes: &[u8]) -> Option<Vec<u64>>
+{
+    bytes.len().is_multiple_of(8).then(|| bytes.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect())
+}
+
+// seed (32 bytes), stream & word position
+impl RngState for rand_chacha::ChaCha20Rng
+{
+    fn state(&self) -> Vec<u8>
+    {
+        let mut state = self.get_seed().to_vec();
+        state.extend_from_slice(&self.get_stream().to_le_bytes());
+        state.extend_from_slice(&self.get_word_pos().to_le_bytes());
+        state
+    }
+    fn from_state(state: &[u8]) -> Option<Self>
+    {
+        if state.len() != 32 + 8 + 16
+        {
+            return None;
+        }
+        let mut rng = <Self as rand::SeedableRng>::from_seed(state[..32].try_into().unwrap());
+        rng.set_stream(u64::from_le_bytes(state[32..40].try_into().unwrap()));
+        rng.set_word_pos(u128::from_le_bytes(state[40..].try_into().unwrap()));
+        Some(rng)
+    }
+}
+
+// Pcg64's state & increment are only reachable through serde
+impl RngState for rand_pcg::Pcg64
+{
+    fn state(&self) -> Vec<u8>
+    {
+        serde_json::to_vec(self).expect("a Pcg64 is always serialisable")
+    }
+    fn from_state(state: &[u8]) -> Option<Self>
+    {
+        serde_json::from_slice(state).ok()
+    }
+}
+
+
+const MT_NN: usize     = 312;
+const MT_MM: usize     = 156;
+const MT_MATRIX_A: u64 = 0xB502_6F5A_A966_19E9;
+const MT_UPPER: u64    = 0xFFFF_FFFF_8000_0000; // most significant 33 bits
+const MT_LOWER: u64    = 0x0000_0000_7FFF_FFFF; // least significant 31 bits
+
+// 64-bit Mersenne Twister (Matsumoto & Nishimura 2000), following the reference mt19937-64.c.
+// seed_from_u64 uses the reference init_genrand64, so Mt64::seed_from_u64(5489) reproduces the reference output.
+#[derive(Clone)]
+pub struct Mt64
+{
+    state: Box<[u64; MT_NN]>,
+    index: usize,
+}
+
+impl Mt64
+{
+    fn generate(&mut self)
+    {
+        let mt  = &mut self.state;
+        let mag = |x: u64| if x & 1 == 0 { 0 } else { MT_MATRIX_A };
+        for i in 0..MT_NN
+        {
+            let x = (mt[i] & MT_UPPER) | (mt[(i + 1) % MT_NN] & MT_LOWER);
+            mt[i] = mt[(i + MT_MM) % MT_NN] ^ (x >> 1) ^ mag(x);
+        }
+        self.index = 0;
+    }
+}
+
+impl rand::RngCore for Mt64
+{
+    #[inline]
+    fn next_u32(&mut self) -> u32
+    {
+        (self.next_u64() >> 32) as u32
+    }
+    #[inline]
+    fn next_u64(&mut self) -> u64
+    {
+        if self.index >= MT_NN
+        {
+            self.generate();
+        }
+        let mut x = self.state[self.index];
+        self.index += 1;
+
+        x ^= (x >> 29) & 0x5555_5555_5555_5555;
+        x ^= (x << 17) & 0x71D6_7FFF_EDA6_0000;
+        x ^= (x << 37) & 0xFFF7_EEE0_0000_0000;
+        x ^ (x >> 43)
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8])
+    {
+        rand::rand_core::impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl rand::SeedableRng for Mt64
+{
+    type Seed = [u8; 8];
+    fn from_seed(seed: Self::Seed) -> Self
+    {
+        Self::seed_from_u64(u64::from_le_bytes(seed))
+    }
+    fn seed_from_u64(seed: u64) -> Self
+    {
+        let mut state = Box::new([0_u64; MT_NN]);
+        state[0] = seed;
+        for i in 1..MT_NN
+        {
+            state[i] = 6_364_136_223_846_793_005_u64
+                .wrapping_mul(state[i-1] ^ (state[i-1] >> 62))
+                .wrapping_add(i as u64);
+        }
+        Self { state, index: MT_NN }
+    }
+}
+
+// the 312 words of the state, then the index of the next one
+impl RngState for Mt64
+{
+    fn state(&self) -> Vec<u8>
+    {
+        let mut state = words_to_bytes(self.state.as_slice());
+        state.extend_from_slice(&(self.index as u64).to_le_bytes());
+        state
+    }
+    fn from_state(state: &[u8]) -> Option<Self>
+    {
+        let words = bytes_to_words(state).filter(|words| words.len() == MT_NN + 1)?;
+        let index = words[MT_NN] as usize;
+        (index <= MT_NN).then(|| Self { state: Box::new(words[..MT_NN].try_into().unwrap()), index })
+    }
+}
+
+
+// xoshiro256++ (Blackman & Vigna 2018), following the reference xoshiro256plusplus.c & seeded as rand's SmallRng
+// on 64-bit targets: the same seed gives the same stream, but the state of a SmallRng cannot be saved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xoshiro256PlusPlus
+{
+    s: [u64; 4],
+}
+
+impl rand::RngCore for Xoshiro256PlusPlus
+{
+    #[inline]
+    fn next_u32(&mut self) -> u32
+    {
+        (self.next_u64() >> 32) as u32
+    }
+    #[inline]
+    fn next_u64(&mut self) -> u64
+    {
+        let s      = &mut self.s;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+        let t      = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3]   = s[3].rotate_left(45);
+        result
+    }
+    fn fill_bytes(&mut self, dst: &mut [u8])
+    {
+        rand::rand_core::impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl rand::SeedableRng for Xoshiro256PlusPlus
+{
+    type Seed = [u8; 32];
+    fn from_seed(seed: Self::Seed) -> Self
+    {
+        let s: [u64; 4] = std::array::from_fn(|i| u64::from_le_bytes(seed[8*i..8*i + 8].try_into().unwrap()));
+        match s == [0; 4]
+        {
+            true  => Self::seed_from_u64(0), // the all-zero state is a fixed point
+            false => Self { s },
+        }
+    }
+    // SplitMix64 stream
+    fn seed_from_u64(mut seed: u64) -> Self
+    {
+        let s = std::array::from_fn(|_|
+        {
+            seed = seed.wrapping_add(GOLDEN_GAMMA);
+            splitmix64(seed)
+        });
+        Self { s }
+    }
+}
+
+impl RngState for Xoshiro256PlusPlus
+{
+    fn state(&self) -> Vec<u8>
+    {
+        words_to_bytes(&self.s)
+    }
+    fn from_state(state: &[u8]) -> Option<Self>
+    {
+        let words = bytes_to_words(state).filter(|words| words.len() == 4 && words.iter().any(|&word| word != 0))?;
+        Some(Self { s: words.try_into().unwrap() })
+    }
+}
@@ -0,0 +1,835 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_pcg::Pcg64;
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::checkpoint::Checkpoint;
+use crate::configuration::{configuration_file_name_for, write_configuration, InitialState};
+use std::time::{Duration, Instant};
+
+use crate::monte_carlo_results::{MonteCarloResults, MEASUREMENTS_COLUMN};
+use crate::observables::{ErrorEstimates, Histograms, Magnetisation, Measurement, Observable, StructureFactor, Summary, Thermodynamics};
+use crate::random::{derive_seed, Mt64, RngKind, RngState, Xoshiro256PlusPlus};
+use crate::snapshot::Snapshots;
+use crate::statistics::integrated_autocorrelation_time;
+use crate::swendsen_wang_algorithm::{
+    BitPackedIsingArray2D, BondActivation, ClusterUpdate, IsingArray2D, Label, LabellingStrategy, LatticeBackend,
+    ParallelSwendsenWang, SpinLattice, SwendsenWangAlgorithm, UnionFindStrategy, J,
+};
+use crate::thermalisation::{EquilibrationTest, Thermalisation};
+use crate::time_series::{TimeSeriesFormat, TimeSeriesWriter};
+
+
+// Creates the observable of one temperature
+type ObservableFactory = Box<dyn Fn() -> Box<dyn Observable> + Send + Sync>;
+
+
+// How the clusters are built & stored, see the corresponding types for the details
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler
+{
+    pub lattice_backend: LatticeBackend,
+    pub strips: usize,                   // > 1: label strips of rows in parallel (ParallelSwendsenWang), default labelling only
+    pub union_find: UnionFindStrategy,
+    pub labelling: LabellingStrategy,
+    pub bond_activation: BondActivation,
+}
+
+impl Default for Sampler
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            lattice_backend: LatticeBackend::default(),
+            strips: 1,
+            union_find: UnionFindStrategy::default(),
+            labelling: LabellingStrategy::default(),
+            bond_activation: BondActivation::default(),
+        }
+    }
+}
+
+
+// Run length control: measure_steps becomes the minimum number of measurement sweeps, each temperature then keeps
+// sweeping until the relative error (ErrorEstimates) of every target result is below its value,
+// or until max_measure_steps measurement sweeps or time_limit (wall-clock, per temperature, counted from the start
+// of the run or of its resumption). The errors are checked every CHECK_INTERVAL measurement sweeps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrecisionTarget
+{
+    pub targets: Vec<(String, f64)>, // (result, relative error), for ex. ("specific_heat", 0.005)
+    pub max_measure_steps: usize,
+    pub time_limit: Option<Duration>,
+}
+
+impl PrecisionTarget
+{
+    pub const CHECK_INTERVAL: usize = 100;
+
+    // "specific_heat=0.005, susceptibility=0.01"
+    pub fn parse_targets(targets: &str) -> Result<Vec<(String, f64)>, String>
+    {
+        targets.split([',', ';']).filter(|target| !target.trim().is_empty()).map(|target|
+        {
+            let (name, error) = target.split_once('=').ok_or_else(|| format!("Expected result=relative_error, got \"{}\"", target.trim()))?;
+            let error         = error.trim().parse::<f64>().map_err(|err| format!("Could not parse the target of {}: {err}", name.trim()))?;
+            Ok((name.trim().to_string(), error))
+        }).collect()
+    }
+    // as parsed by parse_targets, without commas for the legacy header
+    pub fn targets_description(&self) -> String
+    {
+        self.targets.iter().map(|(name, error)| format!("{name}={error}")).collect::<Vec<_>>().join(";")
+    }
+    pub fn is_reached(&self, results: &MonteCarloResults<f64>) -> bool
+    {
+        self.targets.iter().all(|(name, target)|
+        {
+            let relative_error = results.get(&format!("{name}_err")).zip(results.get(name)).map(|(error, value)| error / value.abs());
+            relative_error.is_some_and(|relative_error| relative_error <= *target)
+        })
+    }
+}
+
+
+// Sweeps between two measurements (the measurement being done on the last of them):
+//      Every(n): every n-th sweep, 1 measures every sweep
+//      Auto:     2 tau_int sweeps (rounded up), tau_int being the larger integrated autocorrelation time of the energy & |M|
+//                over PILOT_SWEEPS sweeps after the thermalisation. The pilot sweeps count as thermalisation sweeps,
+//                the interval of each temperature is reported ("sweeps_per_measurement").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasurementInterval
+{
+    Every(usize),
+    Auto,
+}
+
+impl MeasurementInterval
+{
+    pub const PILOT_SWEEPS: usize = 1000;
+}
+
+impl Default for MeasurementInterval
+{
+    fn default() -> Self
+    {
+        MeasurementInterval::Every(1)
+    }
+}
+
+impl std::fmt::Display for MeasurementInterval
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            MeasurementInterval::Every(sweeps) => write!(f, "{sweeps}"),
+            MeasurementInterval::Auto          => write!(f, "auto"),
+        }
+    }
+}
+
+impl std::str::FromStr for MeasurementInterval
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "auto" => Ok(MeasurementInterval::Auto),
+            other  => match other.parse::<usize>()
+            {
+                Ok(sweeps) if sweeps > 0 => Ok(MeasurementInterval::Every(sweeps)),
+                _ => Err(format!("Unknown sweeps per measurement \"{other}\" (expected a positive number or auto)")),
+            },
+        }
+    }
+}
+
+
+// A Swendsen-Wang run over a set of temperatures, each temperature being simulated independently (in parallel,
+// or one after the other when annealing) with its own random stream derived from the master seed:
+//      let results = Simulation::builder()
+//          .lattice(64, 64)
+//          .temperatures(&[2.0, 2.269, 2.5])
+//          .sweeps(1000, 10000)
+//          .seed(42)
+//          .observable(MyObservable::default)
+//          .build()?
+//          .run();
+// Thermodynamics, StructureFactor & Magnetisation are always measured (in this order), before the observables added to the builder.
+// ErrorEstimates (with a precision target) & Histograms (if the builder asks for them) come next.
+pub struct Simulation
+{
+    rows: usize,
+    cols: usize,
+    temperatures: Vec<f64>,
+    therm_steps: usize,          // upper bound with automatic thermalisation
+    thermalisation: Thermalisation,
+    measure_steps: usize,        // number of measurements
+    measurement_interval: MeasurementInterval,
+    measure_corr_length: bool,
+    fourier_interval: usize,     // Fourier transform every n-th measurement (0 or 1: every one)
+    observables: Vec<ObservableFactory>,
+    seed: u64,                   // master seed: each temperature gets its own stream derived from it
+    rng: RngKind,
+    sampler: Sampler,
+    output_file: Option<String>, // names the time series, checkpoint & histogram files
+    time_series_format: Option<TimeSeriesFormat>,
+    checkpoint_interval: usize,  // 0: no checkpoints
+    resume: bool,
+    magnetisation_bins: usize,   // 0: no P(m) histogram
+    energy_bins: usize,          // 0: no P(E) histogram
+    precision: Option<PrecisionTarget>,
+    initial_state: InitialState,
+    initial_configuration: Option<IsingArray2D>, // of initial_state, None if random
+    anneal: bool,                // temperatures one after the other, each starting from the last configuration of the previous one
+    save_configurations: bool,   // final configuration of every temperature, next to the output file
+    snapshots: Option<Snapshots>,
+}
+
+#[derive(Default)]
+pub struct SimulationBuilder
+{
+    rows: usize,
+    cols: usize,
+    temperatures: Vec<f64>,
+    therm_steps: usize,
+    thermalisation: Thermalisation,
+    measure_steps: usize,
+    measurement_interval: MeasurementInterval,
+    measure_corr_length: bool,
+    fourier_interval: usize,
+    observables: Vec<ObservableFactory>,
+    seed: Option<u64>,
+    rng: RngKind,
+    sampler: Sampler,
+    output_file: Option<String>,
+    time_series_format: Option<TimeSeriesFormat>,
+    checkpoint_interval: usize,
+    resume: bool,
+    magnetisation_bins: usize,
+    energy_bins: usize,
+    precision: Option<PrecisionTarget>,
+    initial_state: InitialState,
+    anneal: bool,
+    save_configurations: bool,
+    snapshots: Option<Snapshots>,
+}
+
+impl SimulationBuilder
+{
+    pub fn lattice(mut self, rows: usize, cols: usize) -> Self
+    {
+        self.rows = rows;
+        self.cols = cols;
+        self
+    }
+    pub fn temperatures(mut self, temperatures: &[f64]) -> Self
+    {
+        self.temperatures = temperatures.to_vec();
+        self
+    }
+    pub fn sweeps(mut self, therm_steps: usize, measure_steps: usize) -> Self
+    {
+        self.therm_steps   = therm_steps;
+        self.measure_steps = measure_steps;
+        self
+    }
+    // Auto: therm_steps becomes the maximum number of thermalisation sweeps, see Thermalisation
+    pub fn thermalisation(mut self, thermalisation: Thermalisation) -> Self
+    {
+        self.thermalisation = thermalisation;
+        self
+    }
+    // measure_steps measurements, sweeps_per_measurement sweeps apart
+    pub fn sweeps_per_measurement(mut self, measurement_interval: MeasurementInterval) -> Self
+    {
+        self.measurement_interval = measurement_interval;
+        self
+    }
+    pub fn measure_structure_factor(mut self, measure_corr_length: bool) -> Self
+    {
+        self.measure_corr_length = measure_corr_length;
+        self
+    }
+    // The Fourier transform (structure factor & correlation length) costs complex arithmetic at every site:
+    // only take it every fourier_interval-th measurement
+    pub fn fourier_interval(mut self, fourier_interval: usize) -> Self
+    {
+        self.fourier_interval = fourier_interval;
+        self
+    }
+    // Every temperature gets its own observable from `factory`, for ex. .observable(MyObservable::default)
+    pub fn observable<O: Observable + 'static, F: Fn() -> O + Send + Sync + 'static>(mut self, factory: F) -> Self
+    {
+        self.observables.push(Box::new(move || Box::new(factory())));
+        self
+    }
+    // default: drawn from the OS when building
+    pub fn seed(mut self, seed: u64) -> Self
+    {
+        self.seed = Some(seed);
+        self
+    }
+    pub fn rng(mut self, rng: RngKind) -> Self
+    {
+        self.rng = rng;
+        self
+    }
+    pub fn sampler(mut self, sampler: Sampler) -> Self
+    {
+        self.sampler = sampler;
+        self
+    }
+    pub fn output_file(mut self, output_file: &str) -> Self
+    {
+        self.output_file = Some(output_file.to_string());
+        self
+    }
+    pub fn time_series(mut self, format: TimeSeriesFormat) -> Self
+    {
+        self.time_series_format = Some(format);
+        self
+    }
+    pub fn checkpoint_interval(mut self, checkpoint_interval: usize) -> Self
+    {
+        self.checkpoint_interval = checkpoint_interval;
+        self
+    }
+    pub fn resume(mut self, resume: bool) -> Self
+    {
+        self.resume = resume;
+        self
+    }
+    // P(m) over [-1, 1] & P(E) over [-2, 2] per spin, written next to the output file (0 bins: not measured)
+    pub fn histograms(mut self, magnetisation_bins: usize, energy_bins: usize) -> Self
+    {
+        self.magnetisation_bins = magnetisation_bins;
+        self.energy_bins        = energy_bins;
+        self
+    }
+    // measure_steps becomes the minimum number of measurement sweeps, see PrecisionTarget
+    pub fn target_precision(mut self, precision: PrecisionTarget) -> Self
+    {
+        self.precision = Some(precision);
+        self
+    }
+    pub fn initial_state(mut self, initial_state: InitialState) -> Self
+    {
+        self.initial_state = initial_state;
+        self
+    }
+    // Annealing: the temperatures are simulated one after the other, in the given order (each one in parallel with strips),
+    // every temperature but the first one starting from the final configuration of the previous one
+    pub fn anneal(mut self, anneal: bool) -> Self
+    {
+        self.anneal = anneal;
+        self
+    }
+    // Writes the final configuration of every temperature, see configuration_file_name_for & write_configuration
+    pub fn save_configurations(mut self, save_configurations: bool) -> Self
+    {
+        self.save_configurations = save_configurations;
+        self
+    }
+    // Images of the configuration (& clusters) during the measurements, see Snapshots
+    pub fn snapshots(mut self, snapshots: Snapshots) -> Self
+    {
+        self.snapshots = Some(snapshots);
+        self
+    }
+    pub fn build(self) -> Result<Simulation, String>
+    {
+        if self.rows < 2 || self.cols < 2
+        {
+            return Err(format!("Lattice {}x{} is too small (at least 2x2)", self.rows, self.cols));
+        }
+        if self.temperatures.is_empty()
+        {
+            return Err("No temperatures to simulate".to_string());
+        }
+        if let Some(temp) = self.temperatures.iter().find(|t| !(t.is_finite() && **t > 0_f64))
+        {
+            return Err(format!("Temperatures should be positive (got {temp})"));
+        }
+        if self.measure_steps == 0
+        {
+            return Err("At least one measurement sweep is needed".to_string());
+        }
+        if self.measurement_interval == MeasurementInterval::Every(0)
+        {
+            return Err("At least one sweep per measurement is needed".to_string());
+        }
+        if let Some(precision) = self.precision.as_ref()
+        {
+            if precision.targets.is_empty()
+            {
+                return Err("A precision target needs at least one result".to_string());
+            }
+            if let Some((name, _)) = precision.targets.iter().find(|(name, _)| !ErrorEstimates::OBSERVABLES.contains(&name.as_str()))
+            {
+                return Err(format!("No error estimate for \"{name}\" (expected one of {:?})", ErrorEstimates::OBSERVABLES));
+            }
+            if let Some((name, error)) = precision.targets.iter().find(|(_, error)| !(error.is_finite() && *error > 0_f64))
+            {
+                return Err(format!("The relative error of {name} should be positive (got {error})"));
+            }
+            if precision.max_measure_steps < self.measure_steps
+            {
+                return Err(format!("max_measure_steps ({}) is below measure_steps ({})", precision.max_measure_steps, self.measure_steps));
+            }
+        }
+        if let Some(snapshots) = self.snapshots.as_ref()
+        {
+            if snapshots.interval == 0
+            {
+                return Err("Snapshots need an interval of at least one sweep".to_string());
+            }
+            if let Some(temp) = snapshots.temperatures.iter().find(|temp| !self.temperatures.contains(temp))
+            {
+                return Err(format!("No temperature {temp} to take snapshots at"));
+            }
+        }
+        // the strips are labelled by their own union-find, run_parameters would record a strategy which is not used
+        let Sampler { strips, labelling, union_find, .. } = self.sampler;
+        if strips > 1 && (labelling != LabellingStrategy::default() || union_find != UnionFindStrategy::default())
+        {
+            return Err(format!("Strips are labelled in parallel: labelling {} & union_find {} cannot be used with {strips} strips",
+                labelling.name(), union_find.name()));
+        }
+        let needs_output_file = self.time_series_format.is_some() || self.checkpoint_interval > 0 || self.resume
+            || self.magnetisation_bins > 0 || self.energy_bins > 0 || self.save_configurations || self.snapshots.is_some();
+        if needs_output_file && self.output_file.is_none()
+        {
+            return Err("Time series, checkpoints, histograms, configurations & snapshots need an output file to be named after".to_string());
+        }
+        let initial_configuration = self.initial_state.configuration(self.rows, self.cols)
+            .map_err(|err| format!("Could not load the initial configuration: {err}"))?;
+
+        Ok(Simulation
+        {
+            rows: self.rows,
+            cols: self.cols,
+            temperatures: self.temperatures,
+            therm_steps: self.therm_steps,
+            thermalisation: self.thermalisation,
+            measure_steps: self.measure_steps,
+            measurement_interval: self.measurement_interval,
+            measure_corr_length: self.measure_corr_length,
+            fourier_interval: self.fourier_interval.max(1),
+            observables: self.observables,
+            seed: self.seed.unwrap_or_else(rand::random),
+            rng: self.rng,
+            sampler: self.sampler,
+            output_file: self.output_file,
+            time_series_format: self.time_series_format,
+            checkpoint_interval: self.checkpoint_interval,
+            resume: self.resume,
+            magnetisation_bins: self.magnetisation_bins,
+            energy_bins: self.energy_bins,
+            precision: self.precision,
+            initial_state: self.initial_state,
+            initial_configuration,
+            anneal: self.anneal,
+            save_configurations: self.save_configurations,
+            snapshots: self.snapshots,
+        })
+    }
+}
+
+
+impl Simulation
+{
+    pub fn builder() -> SimulationBuilder
+    {
+        SimulationBuilder::default()
+    }
+    #[inline]
+    pub fn shape(&self) -> (usize, usize)
+    {
+        (self.rows, self.cols)
+    }
+    #[inline]
+    pub fn temperatures(&self) -> &[f64]
+    {
+        &self.temperatures
+    }
+    #[inline]
+    pub fn thermalisation(&self) -> Thermalisation
+    {
+        self.thermalisation
+    }
+    #[inline]
+    pub fn seed(&self) -> u64
+    {
+        self.seed
+    }
+    #[inline]
+    pub fn rng(&self) -> RngKind
+    {
+        self.rng
+    }
+    #[inline]
+    pub fn sampler(&self) -> Sampler
+    {
+        self.sampler
+    }
+    // Every setting of the run, named as in the parameter file, for the metadata of the output file
+    pub fn run_parameters(&self) -> Vec<(&'static str, String)>
+    {
+        vec![
+            ("rows",                self.rows.to_string()),
+            ("cols",                self.cols.to_string()),
+            ("therm_steps",         self.therm_steps.to_string()),
+            ("thermalisation",      self.thermalisation.name().to_string()),
+            ("measure_steps",       self.measure_steps.to_string()),
+            ("sweeps_per_measurement", self.measurement_interval.to_string()),
+            ("measure_struct_fact", self.measure_corr_length.to_string()),
+            ("fourier_interval",    self.fourier_interval.to_string()),
+            ("seed",                self.seed.to_string()),
+            ("rng",                 self.rng.name().to_string()),
+            ("lattice_backend",     self.sampler.lattice_backend.name().to_string()),
+            ("strips",              self.sampler.strips.to_string()),
+            ("union_find",          self.sampler.union_find.name().to_string()),
+            ("labelling",           self.sampler.labelling.name().to_string()),
+            ("bond_activation",     self.sampler.bond_activation.name().to_string()),
+            ("time_series",         self.time_series_format.map_or("none", |format| format.extension()).to_string()),
+            ("checkpoint_interval", self.checkpoint_interval.to_string()),
+            ("magnetisation_bins",  self.magnetisation_bins.to_string()),
+            ("energy_bins",         self.energy_bins.to_string()),
+            ("target_precision",    self.precision.as_ref().map_or("none".to_string(), PrecisionTarget::targets_description)),
+            ("max_measure_steps",   self.precision.as_ref().map_or(self.measure_steps, |precision| precision.max_measure_steps).to_string()),
+            ("time_limit",          self.precision.as_ref().and_then(|precision| precision.time_limit).map_or("none".to_string(), |limit| limit.as_secs_f64().to_string())),
+            ("initial_state",       self.initial_state.description()),
+            ("anneal",              self.anneal.to_string()),
+            ("save_configurations", self.save_configurations.to_string()),
+            ("snapshot_interval",   self.snapshots.as_ref().map_or(0, |snapshots| snapshots.interval).to_string()),
+            ("snapshot_temperatures", self.snapshots.as_ref().map_or("none".to_string(), Snapshots::temperatures_description)),
+            ("snapshot_clusters",   self.snapshots.as_ref().is_some_and(|snapshots| snapshots.clusters).to_string()),
+        ]
+    }
+
+    // One result per temperature, in the order of the temperatures.
+    // With automatic thermalisation, each result ends with the sweeps used ("thermalisation_sweeps"),
+    // with an automatic measurement interval, with its sweeps per measurement ("sweeps_per_measurement"),
+    // with a precision target, with the number of measurements ("measurements", see ResultsFile::merge).
+    // Panics if the time series, checkpoint, histogram, configuration or snapshot files cannot be written.
+    pub fn run(&self) -> Vec<MonteCarloResults<f64>>
+    {
+        if self.anneal
+        {
+            let mut configuration = self.initial_configuration.clone();
+            return self.temperatures.iter().enumerate().map(|(temp_index, &temp)|
+            {
+                let (result, last_configuration) = self.run_temperature(temp_index, temp, configuration.take());
+                configuration = Some(last_configuration);
+                result
+            }).collect();
+        }
+
+        let mut results = vec![MonteCarloResults::<f64>::new(); self.temperatures.len()];
+        (&self.temperatures, &mut results).into_par_iter().enumerate().for_each(|(temp_index, (&temp, result))|
+        {
+            *result = self.run_temperature(temp_index, temp, self.initial_configuration.clone()).0;
+        });
+
+        results
+    }
+
+    // results & final configuration, starting from `initial` (random if None)
+    fn run_temperature(&self, temp_index: usize, temp: f64, initial: Option<IsingArray2D>) -> (MonteCarloResults<f64>, IsingArray2D)
+    {
+        match self.sampler.lattice_backend
+        {
+            LatticeBackend::Standard => self.run_with_updater::<IsingArray2D, usize>(temp_index, temp, initial),
+            LatticeBackend::Compact  => self.run_with_updater::<BitPackedIsingArray2D, u32>(temp_index, temp, initial),
+        }
+    }
+
+    fn new_observables(&self) -> Vec<Box<dyn Observable>>
+    {
+        let defaults: [Box<dyn Observable>; 3] = [Box::new(Thermodynamics::default()), Box::new(StructureFactor::default()), Box::new(Magnetisation::default())];
+        let errors     = self.precision.is_some().then(|| Box::new(ErrorEstimates::default()) as Box<dyn Observable>);
+        let histograms = (self.magnetisation_bins > 0 || self.energy_bins > 0)
+            .then(|| Box::new(Histograms::new(self.magnetisation_bins, self.energy_bins)) as Box<dyn Observable>);
+        defaults.into_iter().chain(errors).chain(histograms).chain(self.observables.iter().map(|factory| factory())).collect()
+    }
+
+    // the results are safe: checkpoints are no longer needed
+    pub fn remove_checkpoints(&self)
+    {
+        if let Some(output_file) = self.output_file.as_ref()
+        {
+            self.temperatures.iter().for_each(|&temp|
+            {
+                let _ = std::fs::remove_file(Checkpoint::file_name_for(output_file, temp));
+            });
+        }
+    }
+
+    fn run_with_updater<S: SpinLattice, L: Label>(&self, temp_index: usize, temp: f64, initial: Option<IsingArray2D>) -> (MonteCarloResults<f64>, IsingArray2D)
+    {
+        let Sampler { strips, union_find, labelling, .. } = self.sampler;
+        match strips
+        {
+            0 | 1  => self.run_with_rng::<S, _>(temp_index, temp, initial, SwendsenWangAlgorithm::<L>::with_labelling(self.rows, self.cols, labelling, union_find)),
+            strips => self.run_with_rng::<S, _>(temp_index, temp, initial, ParallelSwendsenWang::<L>::new(self.rows, self.cols, strips)),
+        }
+    }
+
+    fn run_with_rng<S: SpinLattice, U: ClusterUpdate<S>>(&self, temp_index: usize, temp: f64, initial: Option<IsingArray2D>, swendsen_wang: U) -> (MonteCarloResults<f64>, IsingArray2D)
+    {
+        match self.rng
+        {
+            RngKind::Small           => self.run_single_temperature::<Xoshiro256PlusPlus, S, U>(temp_index, temp, initial, swendsen_wang),
+            RngKind::ChaCha          => self.run_single_temperature::<ChaCha20Rng, S, U>(temp_index, temp, initial, swendsen_wang),
+            RngKind::MersenneTwister => self.run_single_temperature::<Mt64, S, U>(temp_index, temp, initial, swendsen_wang),
+            RngKind::Pcg             => self.run_single_temperature::<Pcg64, S, U>(temp_index, temp, initial, swendsen_wang),
+        }
+    }
+
+    fn run_single_temperature<R: Rng + SeedableRng + RngState + Send, S: SpinLattice, U: ClusterUpdate<S>>(&self, temp_index: usize, temp: f64, initial: Option<IsingArray2D>, mut swendsen_wang: U) -> (MonteCarloResults<f64>, IsingArray2D)
+    {
+        let &Simulation { rows, cols, measure_corr_length, time_series_format, checkpoint_interval, resume, seed, .. } = self;
+        swendsen_wang.set_bond_activation(self.sampler.bond_activation);
+
+        let output_file     = self.output_file.as_deref().unwrap_or_default();
+        let checkpoint_file = Checkpoint::file_name_for(output_file, temp);
+        let checkpoint      = match resume
+        {
+            true  => Checkpoint::read_from_file(&checkpoint_file).map(Some).unwrap_or_else(|err| match err.kind()
+            {
+                std::io::ErrorKind::NotFound => None,
+                _ => panic!("!! Could not read checkpoint {checkpoint_file}: {err}"),
+            }),
+            false => None,
+        };
+
+        let mut observables = self.new_observables();
+        let needs_clusters  = observables.iter().any(|observable| observable.needs_clusters());
+        let needs_fourier   = measure_corr_length || observables.iter().any(|observable| observable.needs_fourier_transform());
+        let snapshots       = self.snapshots.as_ref().filter(|snapshots| snapshots.takes_temperature(temp));
+
+        let proba_add = 1f64 - (-2_f64*J/temp).exp();
+
+        let (mut rng, mut spins, sweeps_done, therm_steps, sweeps_per_measurement) = match checkpoint
+        {
+            Some(checkpoint) =>
+            {
+                let therm_steps            = checkpoint.therm_steps;
+                let sweeps_per_measurement = checkpoint.sweeps_per_measurement;
+                let matches_interval       = match self.measurement_interval
+                {
+                    MeasurementInterval::Every(sweeps) => sweeps == sweeps_per_measurement,
+                    MeasurementInterval::Auto          => true,
+                };
+                let matches_parameters = checkpoint.spins.shape() == (rows as i32, cols as i32) && checkpoint.temperature == temp && matches_interval
+                    && checkpoint.sweeps_done <= therm_steps + self.max_measure_steps() * sweeps_per_measurement;
+                assert!(matches_parameters, "!! Checkpoint {checkpoint_file} does not match the parameters");
+                let mut state = checkpoint.accumulators.as_slice();
+                for observable in observables.iter_mut()
+                {
+                    let used = observable.load_state(state).expect("!! Corrupted checkpoint accumulators");
+                    state    = &state[used..];
+                }
+                assert!(state.is_empty(), "!! Checkpoint {checkpoint_file} does not match the observables");
+                let rng = R::from_state(&checkpoint.rng_state)
+                    .unwrap_or_else(|| panic!("!! Checkpoint {checkpoint_file} does not hold the state of a {} generator", self.rng.name()));
+                (rng, S::from_ising_array(checkpoint.spins), checkpoint.sweeps_done, therm_steps, sweeps_per_measurement)
+            }
+            None =>
+            {
+                let mut rng   = R::seed_from_u64(derive_seed(seed, temp_index, 0));
+                let mut spins = match initial
+                {
+                    Some(initial) => S::from_ising_array(initial),
+                    None          => S::new_randomized(&mut rng, rows, cols),
+                };
+                let (therm_steps, sweeps_done) = match self.thermalisation
+                {
+                    Thermalisation::Fixed => (self.therm_steps, 0),
+                    Thermalisation::Auto  =>
+                    {
+                        let therm_steps = self.thermalise(temp_index, &mut swendsen_wang, &mut rng, &mut spins, proba_add);
+                        (therm_steps, therm_steps)
+                    }
+                };
+                match self.measurement_interval
+                {
+                    MeasurementInterval::Every(sweeps) => (rng, spins, sweeps_done, therm_steps, sweeps),
+                    MeasurementInterval::Auto          =>
+                    {
+                        // the pilot needs a thermalised lattice: a fixed thermalisation is done here, not in the measurement loop
+                        (sweeps_done..therm_steps).for_each(|_| { sweep(&mut swendsen_wang, &mut spins, &mut rng, proba_add); });
+                        let sweeps      = self.decorrelation_sweeps(&mut swendsen_wang, &mut rng, &mut spins, proba_add);
+                        let therm_steps = therm_steps + MeasurementInterval::PILOT_SWEEPS;
+                        (rng, spins, therm_steps, therm_steps, sweeps)
+                    }
+                }
+            }
+        };
+        let measurements_done = |sweeps_done: usize| sweeps_done.saturating_sub(therm_steps) / sweeps_per_measurement;
+        let started = Instant::now();
+
+        let mut time_series = time_series_format.map(|format|
+        {
+            let file_name = TimeSeriesWriter::file_name_for(output_file, temp, format);
+            let records   = measurements_done(sweeps_done);
+            let writer    = match records
+            {
+                0 => TimeSeriesWriter::create(&file_name, format),
+                _ => TimeSeriesWriter::resume(&file_name, format, records),
+            };
+            writer.unwrap_or_else(|err| panic!("!! Could not open time series file {file_name}: {err}"))
+        });
+
+        let mut sweeps_done = sweeps_done;
+        let mut finished    = self.is_finished(temp, measurements_done(sweeps_done), &observables, started);
+        while !finished
+        {
+            let step          = sweeps_done;
+            let is_measuring  = step >= therm_steps && (step + 1 - therm_steps).is_multiple_of(sweeps_per_measurement);
+            let takes_fourier = is_measuring && needs_fourier && measurements_done(step).is_multiple_of(self.fourier_interval);
+            let snapshot      = snapshots.filter(|snapshots| step >= therm_steps && (step + 1 - therm_steps).is_multiple_of(snapshots.interval));
+            swendsen_wang.set_take_fourier_transform(takes_fourier);
+
+            let (energy, spin_sum) = swendsen_wang.perform_swendsen_wang_all(&spins, &mut rng, proba_add);
+            let (spin_q0, spin_qx) = swendsen_wang.flip_cluster_and_take_fourier(&mut spins, &mut rng);
+            let draws_clusters     = snapshot.is_some_and(|snapshots| snapshots.clusters);
+            let clusters           = ((is_measuring && needs_clusters) || draws_clusters).then(|| swendsen_wang.cluster_map(&spins));
+            swendsen_wang.reset();
+
+            if let Some(snapshots) = snapshot
+            {
+                snapshots.write(output_file, temp, step + 1, &spins.to_ising_array(), clusters.as_ref().filter(|_| draws_clusters))
+                    .unwrap_or_else(|err| panic!("!! Could not write the snapshot of T={temp} after {} sweeps: {err}", step + 1));
+            }
+
+            if is_measuring
+            {
+                if let Some(writer) = time_series.as_mut()
+                {
+                    writer.write_sweep(energy, spin_sum, spin_q0, spin_qx).expect("!! Could not write to time series file");
+                }
+                let measurement = Measurement { temperature: temp, spins: &spins, energy, spin_sum, spin_q0, spin_qx, has_fourier_transform: takes_fourier, clusters: clusters.as_ref() };
+                observables.iter_mut().for_each(|observable| observable.measure(&measurement));
+            }
+
+            sweeps_done = step + 1;
+            finished    = is_measuring && self.is_finished(temp, measurements_done(sweeps_done), &observables, started);
+            if checkpoint_interval > 0 && (sweeps_done % checkpoint_interval == 0 || finished)
+            {
+                if let Some(writer) = time_series.as_mut()
+                {
+                    writer.flush().expect("!! Could not write to time series file");
+                }
+                let accumulators = observables.iter().flat_map(|observable| observable.save_state()).collect();
+                let checkpoint   = Checkpoint { temperature: temp, sweeps_done, therm_steps, sweeps_per_measurement, rng_state: rng.state(), accumulators, spins: spins.to_ising_array() };
+                checkpoint.write_to_file(&checkpoint_file).unwrap_or_else(|err| panic!("!! Could not write checkpoint {checkpoint_file}: {err}"));
+            }
+        }
+        if let Some(writer) = time_series.as_mut()
+        {
+            writer.flush().expect("!! Could not write to time series file");
+        }
+        let last_configuration = spins.to_ising_array();
+        if self.save_configurations
+        {
+            let file_name = configuration_file_name_for(output_file, temp);
+            let comments  = [("temperature", temp.to_string()), ("sweeps", sweeps_done.to_string()), ("seed", seed.to_string())];
+            write_configuration(&file_name, &last_configuration, &comments).unwrap_or_else(|err| panic!("!! Could not write configuration {file_name}: {err}"));
+        }
+        let num_measurements = measurements_done(sweeps_done);
+        let summary          = Summary { temperature: temp, rows, cols, num_measurements };
+        if let Some(output_file) = self.output_file.as_deref()
+        {
+            for observable in &observables
+            {
+                observable.write_files(&summary, output_file).unwrap_or_else(|err| panic!("!! Could not write the files of {output_file} at T={temp}: {err}"));
+            }
+        }
+        let mut results: MonteCarloResults<f64> = observables.iter().flat_map(|observable| observable.results(&summary)).collect();
+        if self.thermalisation == Thermalisation::Auto
+        {
+            results.push("thermalisation_sweeps", therm_steps as f64);
+        }
+        if self.measurement_interval == MeasurementInterval::Auto
+        {
+            results.push("sweeps_per_measurement", sweeps_per_measurement as f64);
+        }
+        if self.precision.is_some()
+        {
+            results.push(MEASUREMENTS_COLUMN, num_measurements as f64);
+        }
+        (results, last_configuration)
+    }
+
+    fn max_measure_steps(&self) -> usize
+    {
+        self.precision.as_ref().map_or(self.measure_steps, |precision| precision.max_measure_steps)
+    }
+
+    // measure_steps measurements, or with a precision target: until it is reached or the budget is spent
+    fn is_finished(&self, temp: f64, num_measurements: usize, observables: &[Box<dyn Observable>], started: Instant) -> bool
+    {
+        let Some(precision) = self.precision.as_ref() else
+        {
+            return num_measurements >= self.measure_steps;
+        };
+        if num_measurements < self.measure_steps
+        {
+            return false;
+        }
+        if num_measurements >= precision.max_measure_steps || precision.time_limit.is_some_and(|limit| started.elapsed() >= limit)
+        {
+            return true;
+        }
+        if !num_measurements.is_multiple_of(PrecisionTarget::CHECK_INTERVAL)
+        {
+            return false;
+        }
+        let summary = Summary { temperature: temp, rows: self.rows, cols: self.cols, num_measurements };
+        precision.is_reached(&observables.iter().flat_map(|observable| observable.results(&summary)).collect())
+    }
+
+    // Automatic thermalisation: `spins` (the hot start) & a cold replica with its own random stream are updated in turn,
+    // until their energies agree or therm_steps sweeps are done. Returns the number of sweeps.
+    // `spins` & `rng` go through the same sweeps as with a fixed thermalisation of that many sweeps.
+    fn thermalise<R: Rng + SeedableRng + Send, S: SpinLattice, U: ClusterUpdate<S>>(&self, temp_index: usize, swendsen_wang: &mut U, rng: &mut R, spins: &mut S, proba_add: f64) -> usize
+    {
+        let mut cold_rng   = R::seed_from_u64(derive_seed(self.seed, temp_index, 1));
+        let mut cold_spins = S::new_polarized(self.rows, self.cols);
+        let mut test       = EquilibrationTest::default();
+        while test.sweeps() < self.therm_steps && !test.is_equilibrated()
+        {
+            let (hot_energy, _)  = sweep(swendsen_wang, spins, rng, proba_add);
+            let (cold_energy, _) = sweep(swendsen_wang, &mut cold_spins, &mut cold_rng, proba_add);
+            test.push(hot_energy, cold_energy);
+        }
+        test.sweeps()
+    }
+
+    // Automatic measurement interval from PILOT_SWEEPS sweeps of a thermalised lattice, see MeasurementInterval
+    fn decorrelation_sweeps<R: Rng + SeedableRng + Send, S: SpinLattice, U: ClusterUpdate<S>>(&self, swendsen_wang: &mut U, rng: &mut R, spins: &mut S, proba_add: f64) -> usize
+    {
+        let (energies, abs_spin_sums): (Vec<f64>, Vec<f64>) = (0..MeasurementInterval::PILOT_SWEEPS)
+            .map(|_| sweep(swendsen_wang, spins, rng, proba_add))
+            .map(|(energy, spin_sum)| (energy, spin_sum.abs()))
+            .unzip();
+        let tau = integrated_autocorrelation_time(&energies).max(integrated_autocorrelation_time(&abs_spin_sums));
+        ((2_f64 * tau).ceil() as usize).max(1)
+    }
+}
+
+// One update of `spins` without measurement, as the thermalisation sweeps of the measurement loop.
+// Returns the energy & signed spin sum of the configuration before the flip.
+fn sweep<R: Rng + SeedableRng + Send, S: SpinLattice, U: ClusterUpdate<S>>(swendsen_wang: &mut U, spins: &mut S, rng: &mut R, proba_add: f64) -> (f64, f64)
+{
+    swendsen_wang.set_take_fourier_transform(false);
+    let sums = swendsen_wang.perform_swendsen_wang_all(spins, rng, proba_add);
+    swendsen_wang.flip_cluster_and_take_fourier(spins, rng);
+    swendsen_wang.reset();
+    sums
+}
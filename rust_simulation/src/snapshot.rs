@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::swendsen_wang_algorithm::{ClusterMap, IsingArray2D};
+
+
+// Netpbm (binary PGM for the spins, PPM for the cluster maps: readable by most viewers, no compression) or PNG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat
+{
+    #[default]
+    Netpbm,
+    Png,
+}
+
+impl ImageFormat
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            ImageFormat::Netpbm => "netpbm",
+            ImageFormat::Png    => "png",
+        }
+    }
+}
+
+impl std::str::FromStr for ImageFormat
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "netpbm" | "pgm" | "ppm" => Ok(ImageFormat::Netpbm),
+            "png"                    => Ok(ImageFormat::Png),
+            other => Err(format!("Unknown image format \"{other}\" (expected netpbm or png)")),
+        }
+    }
+}
+
+
+// 8-bit grey or RGB image, row major, one square of scale x scale pixels per site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image
+{
+    width: usize,
+    height: usize,
+    channels: usize, // 1: grey, 3: RGB
+    pixels: Vec<u8>,
+}
+
+impl Image
+{
+    // up spins white, down spins black
+    pub fn from_spins(spins: &IsingArray2D, scale: usize) -> Self
+    {
+        let (rows, cols) = spins.shape();
+        Self::from_sites(rows as usize, cols as usize, scale, 1, |site| if spins.as_slice()[site] > 0 { &[255] } else { &[0] })
+    }
+    // every cluster in its own colour, see cluster_colour
+    pub fn from_clusters(clusters: &ClusterMap, scale: usize) -> Self
+    {
+        let (rows, cols) = clusters.shape();
+        let colours: Vec<[u8; 3]> = (0..clusters.num_clusters()).map(cluster_colour).collect();
+        Self::from_sites(rows, cols, scale, 3, |site| &colours[clusters.labels()[site]])
+    }
+    // pixel_of(site) gives the `channels` values of the site (palette indices for the animation frames)
+    pub(crate) fn from_sites<'a, F: Fn(usize) -> &'a [u8]>(rows: usize, cols: usize, scale: usize, channels: usize, pixel_of: F) -> Self
+    {
+        let scale           = scale.max(1);
+        let (width, height) = (cols * scale, rows * scale);
+        let mut pixels      = Vec::with_capacity(width * height * channels);
+        for i in 0..rows
+        {
+            let start = pixels.len();
+            (0..cols).for_each(|j| (0..scale).for_each(|_| pixels.extend_from_slice(pixel_of(i * cols + j))));
+            (1..scale).for_each(|_| pixels.extend_from_within(start..start + width * channels));
+        }
+        Self { width, height, channels, pixels }
+    }
+    #[inline]
+    pub fn width(&self) -> usize
+    {
+        self.width
+    }
+    #[inline]
+    pub fn height(&self) -> usize
+    {
+        self.height
+    }
+    #[inline]
+    pub fn channels(&self) -> usize
+    {
+        self.channels
+    }
+    #[inline]
+    pub fn pixels(&self) -> &[u8]
+    {
+        &self.pixels
+    }
+    // "pgm" | "ppm" | "png"
+    pub fn extension(&self, format: ImageFormat) -> &'static str
+    {
+        match (format, self.channels)
+        {
+            (ImageFormat::Png, _)    => "png",
+            (ImageFormat::Netpbm, 1) => "pgm",
+            (ImageFormat::Netpbm, _) => "ppm",
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: ImageFormat) -> std::io::Result<()>
+    {
+        match format
+        {
+            ImageFormat::Netpbm => self.write_netpbm(writer),
+            ImageFormat::Png    => self.write_png(writer),
+        }
+    }
+    pub fn write_to_file(&self, file_name: &str, format: ImageFormat) -> std::io::Result<()>
+    {
+        let mut file = BufWriter::new(File::create(file_name)?);
+        self.write(&mut file, format)?;
+        file.flush()
+    }
+    // binary "P5" (grey) or "P6" (RGB) header, then the raw pixels
+    fn write_netpbm<W: Write>(&self, mut writer: W) -> std::io::Result<()>
+    {
+        let magic = if self.channels == 1 { "P5" } else { "P6" };
+        write!(writer, "{magic}\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)
+    }
+    fn write_png<W: Write>(&self, writer: W) -> std::io::Result<()>
+    {
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(if self.channels == 1 { png::ColorType::Grayscale } else { png::ColorType::Rgb });
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut png_writer = encoder.write_header().map_err(std::io::Error::other)?;
+        png_writer.write_image_data(&self.pixels).map_err(std::io::Error::other)?;
+        png_writer.finish().map_err(std::io::Error::other)
+    }
+}
+
+// Colour of a cluster label: hues spread by the golden ratio, so that clusters labelled one after the other
+// (neighbours, mostly) get well separated colours. Saturation & value alternate to double the number of distinct colours.
+pub fn cluster_colour(label: usize) -> [u8; 3]
+{
+    let hue        = (label as f64 * 0.618_033_988_749_895).fract() * 6_f64;
+    let (sat, val) = if label.is_multiple_of(2) { (0.85, 0.95) } else { (0.6, 0.7) };
+    let sector     = hue.floor();
+    let f          = hue - sector;
+    let (p, q, t)  = (val * (1_f64 - sat), val * (1_f64 - sat * f), val * (1_f64 - sat * (1_f64 - f)));
+    let (r, g, b)  = match sector as usize
+    {
+        0 => (val, t, p),
+        1 => (q, val, p),
+        2 => (p, val, t),
+        3 => (p, q, val),
+        4 => (t, p, val),
+        _ => (val, p, q),
+    };
+    [r, g, b].map(|channel| (channel * 255_f64).round() as u8)
+}
+
+
+// Images of the configuration taken during the measurements, every `interval` sweeps after the thermalisation,
+// at the chosen temperatures (all of them if empty). With `clusters`, the Fortuin-Kasteleyn clusters of the same sweep
+// (whose flips gave the configuration) are drawn next to it, see file_name_for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshots
+{
+    pub interval: usize,
+    pub temperatures: Vec<f64>,
+    pub clusters: bool,
+    pub format: ImageFormat,
+    pub scale: usize,           // pixels per spin (side)
+}
+
+impl Default for Snapshots
+{
+    fn default() -> Self
+    {
+        Self { interval: 1, temperatures: Vec::new(), clusters: false, format: ImageFormat::default(), scale: 1 }
+    }
+}
+
+impl Snapshots
+{
+    pub fn takes_temperature(&self, temp: f64) -> bool
+    {
+        self.temperatures.is_empty() || self.temperatures.contains(&temp)
+    }
+    // for the header, without commas
+    pub fn temperatures_description(&self) -> String
+    {
+        match self.temperatures.is_empty()
+        {
+            true  => "all".to_string(),
+            false => self.temperatures.iter().map(f64::to_string).collect::<Vec<_>>().join(";"),
+        }
+    }
+    // "dir/out_rows=8,cols=8.txt" at T=2.25 after 300 sweeps => "dir/out_rows=8,cols=8_T=2.25_sweep=300_spins.pgm"
+    // ("_clusters.ppm" for the cluster map)
+    pub fn file_name_for(output_file: &str, temp: f64, sweeps_done: usize, image: &str, extension: &str) -> String
+    {
+        let path = Path::new(output_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        path.with_file_name(format!("{stem}_T={temp}_sweep={sweeps_done}_{image}.{extension}")).to_string_lossy().into_owned()
+    }
+    // writes the spins (& clusters) of the sweep
+    pub fn write(&self, output_file: &str, temp: f64, sweeps_done: usize, spins: &IsingArray2D, clusters: Option<&ClusterMap>) -> std::io::Result<()>
+    {
+        let images = std::iter::once(("spins", Image::from_spins(spins, self.scale)))
+            .chain(clusters.map(|clusters| ("clusters", Image::from_clusters(clusters, self.scale))));
+        for (name, image) in images
+        {
+            image.write_to_file(&Self::file_name_for(output_file, temp, sweeps_done, name, image.extension(self.format)), self.format)?;
+        }
+        Ok(())
+    }
+}
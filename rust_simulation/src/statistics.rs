@@ -0,0 +1,268 @@
+// Streaming estimators of the measurement loop
+
+
+// Mean & variance of a stream of values by Welford's update (Welford 1962, Knuth TAOCP vol. 2, 4.2.2):
+//      mean_n = mean_(n-1) + (x - mean_(n-1)) / n
+//      M2_n   = M2_(n-1) + (x - mean_(n-1)) (x - mean_n)
+// Unlike <x²> - <x>² from raw sums, the variance does not suffer from cancellation when the fluctuations
+// are small compared to the mean, as for the total energy of a large lattice.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RunningMoments
+{
+    count: f64,
+    mean: f64,
+    m2: f64,     // sum of squared deviations from the mean
+}
+
+impl RunningMoments
+{
+    #[inline]
+    pub fn push(&mut self, value: f64)
+    {
+        self.count += 1_f64;
+        let delta   = value - self.mean;
+        self.mean  += delta / self.count;
+        self.m2    += delta * (value - self.mean);
+    }
+    #[inline]
+    pub fn count(&self) -> usize
+    {
+        self.count as usize
+    }
+    #[inline]
+    pub fn mean(&self) -> f64
+    {
+        self.mean
+    }
+    // population variance <x²> - <x>² (normalised by n, as the fluctuation formulas need), 0 if empty
+    #[inline]
+    pub fn variance(&self) -> f64
+    {
+        if self.count > 0_f64 { self.m2 / self.count } else { 0_f64 }
+    }
+    // <x²>
+    #[inline]
+    pub fn mean_sqr(&self) -> f64
+    {
+        self.variance() + self.mean * self.mean
+    }
+
+    // (count, mean, M2) for checkpoints
+    pub fn to_array(&self) -> [f64; 3]
+    {
+        [self.count, self.mean, self.m2]
+    }
+    pub fn from_array([count, mean, m2]: [f64; 3]) -> Self
+    {
+        Self { count, mean, m2 }
+    }
+}
+
+
+// Counts of values in `bins` equal bins over [low, high]: values outside the range are counted apart,
+// the upper edge belongs to the last bin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram
+{
+    low: f64,
+    high: f64,
+    counts: Vec<u64>,
+    outside: u64,
+}
+
+impl Histogram
+{
+    pub fn new(low: f64, high: f64, bins: usize) -> Self
+    {
+        assert!(bins > 0 && low < high, "a histogram needs bins & a non empty range");
+        Self { low, high, counts: vec![0; bins], outside: 0 }
+    }
+    #[inline]
+    pub fn push(&mut self, value: f64)
+    {
+        if !(self.low..=self.high).contains(&value)
+        {
+            self.outside += 1;
+            return;
+        }
+        let bins = self.counts.len();
+        let bin  = ((value - self.low) / self.bin_width()) as usize;
+        self.counts[bin.min(bins - 1)] += 1;
+    }
+    #[inline]
+    pub fn bin_width(&self) -> f64
+    {
+        (self.high - self.low) / self.counts.len() as f64
+    }
+    // (lower edge, upper edge) of every bin
+    pub fn bin_edges(&self) -> impl Iterator<Item = (f64, f64)> + '_
+    {
+        let width = self.bin_width();
+        (0..self.counts.len()).map(move |bin| (self.low + bin as f64 * width, self.low + (bin + 1) as f64 * width))
+    }
+    #[inline]
+    pub fn counts(&self) -> &[u64]
+    {
+        &self.counts
+    }
+    #[inline]
+    pub fn outside(&self) -> u64
+    {
+        self.outside
+    }
+    // all values pushed, outside of the range included
+    pub fn total(&self) -> u64
+    {
+        self.counts.iter().sum::<u64>() + self.outside
+    }
+    // probability density of every bin: counts / (total * width), integrates to 1 if nothing fell outside
+    pub fn densities(&self) -> impl Iterator<Item = f64> + '_
+    {
+        let norm = (self.total().max(1) as f64) * self.bin_width();
+        self.counts.iter().map(move |&count| count as f64 / norm)
+    }
+
+    // counts then the number of values outside, for checkpoints
+    pub fn to_vec(&self) -> Vec<f64>
+    {
+        self.counts.iter().chain(std::iter::once(&self.outside)).map(|&count| count as f64).collect()
+    }
+    // reads back to_vec from the front of `state`, returns how many values were used
+    pub fn load(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let used = self.counts.len() + 1;
+        let (&outside, counts) = state.get(..used)?.split_last()?;
+        self.counts.iter_mut().zip(counts).for_each(|(count, &value)| *count = value as u64);
+        self.outside = outside as u64;
+        Some(used)
+    }
+}
+
+
+// Bin averages of K quantities measured together, for series whose length is not known in advance:
+// at most MAX_BINS complete bins are kept, pairs of bins being merged (& the bin size doubled) once they are all full.
+// With bins much longer than the autocorrelation time, the bin averages are independent: jackknife_error then gives
+// the statistical error of any function of the averages (Efron 1982), the unfinished bin being left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binning<const K: usize>
+{
+    bin_size: usize,
+    bins: Vec<[f64; K]>,    // sums over bin_size samples
+    current: [f64; K],
+    current_count: usize,
+}
+
+impl<const K: usize> Default for Binning<K>
+{
+    fn default() -> Self
+    {
+        Self { bin_size: 1, bins: Vec::new(), current: [0_f64; K], current_count: 0 }
+    }
+}
+
+impl<const K: usize> Binning<K>
+{
+    pub const MAX_BINS: usize = 128;
+
+    pub fn push(&mut self, values: [f64; K])
+    {
+        self.current.iter_mut().zip(values).for_each(|(sum, value)| *sum += value);
+        self.current_count += 1;
+        if self.current_count < self.bin_size
+        {
+            return;
+        }
+        self.bins.push(std::mem::replace(&mut self.current, [0_f64; K]));
+        self.current_count = 0;
+        if self.bins.len() == Self::MAX_BINS
+        {
+            self.bins = self.bins.chunks_exact(2).map(|pair| std::array::from_fn(|k| pair[0][k] + pair[1][k])).collect();
+            self.bin_size *= 2;
+        }
+    }
+    #[inline]
+    pub fn bin_size(&self) -> usize
+    {
+        self.bin_size
+    }
+    #[inline]
+    pub fn num_bins(&self) -> usize
+    {
+        self.bins.len()
+    }
+    // averages over the complete bins
+    pub fn means(&self) -> [f64; K]
+    {
+        let samples = (self.bins.len() * self.bin_size) as f64;
+        std::array::from_fn(|k| self.bins.iter().map(|bin| bin[k]).sum::<f64>() / samples)
+    }
+    // Jackknife error of estimator(averages), NaN with less than 2 bins
+    pub fn jackknife_error<F: Fn(&[f64; K]) -> f64>(&self, estimator: F) -> f64
+    {
+        let num_bins = self.bins.len();
+        if num_bins < 2
+        {
+            return f64::NAN;
+        }
+        let totals: [f64; K] = std::array::from_fn(|k| self.bins.iter().map(|bin| bin[k]).sum());
+        let samples          = ((num_bins - 1) * self.bin_size) as f64;
+        let mut estimates    = RunningMoments::default();
+        for bin in &self.bins
+        {
+            let means: [f64; K] = std::array::from_fn(|k| (totals[k] - bin[k]) / samples);
+            estimates.push(estimator(&means));
+        }
+        (estimates.variance() * (num_bins - 1) as f64).sqrt()
+    }
+
+    // bin size, bin count, number of samples in the current bin, the current sums then the bins, for checkpoints
+    pub fn to_vec(&self) -> Vec<f64>
+    {
+        let header = [self.bin_size as f64, self.bins.len() as f64, self.current_count as f64];
+        header.into_iter().chain(self.current).chain(self.bins.iter().flatten().copied()).collect()
+    }
+    // reads back to_vec from the front of `state`, returns how many values were used
+    pub fn load(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[bin_size, num_bins, current_count, ..] = state else
+        {
+            return None;
+        };
+        let (bin_size, num_bins) = (bin_size as usize, num_bins as usize);
+        let used    = 3 + K * (num_bins + 1);
+        let values  = state.get(3..used)?;
+        let current = values[..K].try_into().ok()?;
+        let bins    = values[K..].chunks_exact(K).map(|bin| bin.try_into().unwrap()).collect();
+        *self = Self { bin_size, bins, current, current_count: current_count as usize };
+        Some(used)
+    }
+}
+
+
+// Integrated autocorrelation time tau = 1/2 + sum_t rho(t) of a stationary series, with Sokal's automatic window:
+// the sum stops at the smallest t >= WINDOW tau(t), where the noise of rho(t) starts to dominate (Sokal 1997).
+// x_n & x_(n + 2 tau) are roughly independent. 1/2 for uncorrelated or constant series.
+pub fn integrated_autocorrelation_time(series: &[f64]) -> f64
+{
+    const WINDOW: f64 = 6_f64;
+    let length = series.len();
+    let mut moments = RunningMoments::default();
+    series.iter().for_each(|&x| moments.push(x));
+    let (mean, variance) = (moments.mean(), moments.variance());
+    if length < 2 || variance == 0_f64
+    {
+        return 0.5;
+    }
+
+    let mut tau = 0.5;
+    for t in 1..length
+    {
+        let covariance = series.iter().zip(&series[t..]).map(|(x, y)| (x - mean) * (y - mean)).sum::<f64>() / (length - t) as f64;
+        tau += covariance / variance;
+        if t as f64 >= WINDOW * tau
+        {
+            break;
+        }
+    }
+    tau.max(0.5)
+}
@@ -2,21 +2,70 @@
 
 mod cluster_labels;
 mod ising_array_2d;
+mod bit_packed_ising_array_2d;
+mod spin_lattice;
 mod equivalence_class;
+mod parallel_swendsen_wang;
+mod labelling;
+mod bond_activation;
+mod cluster_map;
 
 use num::complex::Complex64;
 use std::f64::consts::PI;
-use rand::rngs::SmallRng;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 
-pub use cluster_labels::ClusterLabels;
-pub use equivalence_class::EquivalenceClass;
+pub use cluster_labels::{ClusterLabels, Label};
+pub use equivalence_class::{EquivalenceClass, UnionFindStrategy};
 pub use ising_array_2d::IsingArray2D;
+pub use bit_packed_ising_array_2d::BitPackedIsingArray2D;
+pub use spin_lattice::SpinLattice;
+pub use parallel_swendsen_wang::ParallelSwendsenWang;
+pub use labelling::{LabellingStrategy, BondConfiguration, ClusterLabelling, UnionFindLabelling, FloodFill, LabelEquivalence};
+pub use bond_activation::{BondActivation, BondSampler, PerBondSampler, GeometricSkipSampler};
+pub use cluster_map::ClusterMap;
 
 
 pub const J: f64 = 1.0;
 
 
+// Storage used for the spins & cluster labels:
+//      standard: IsingArray2D (i8 per spin) & usize labels
+//      compact:  BitPackedIsingArray2D (1 bit per spin) & u32 labels, for lattices up to 16384² on 8Gb of RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatticeBackend
+{
+    #[default]
+    Standard,
+    Compact,
+}
+
+impl LatticeBackend
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            LatticeBackend::Standard => "standard",
+            LatticeBackend::Compact  => "compact",
+        }
+    }
+}
+
+impl std::str::FromStr for LatticeBackend
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "standard" => Ok(LatticeBackend::Standard),
+            "compact" | "bit_packed" => Ok(LatticeBackend::Compact),
+            other => Err(format!("Unknown lattice backend \"{other}\" (expected standard or compact)")),
+        }
+    }
+}
+
+
 #[allow(dead_code)]
 trait Direction 
 {
@@ -52,33 +101,85 @@ impl Direction for (i32, i32)
 
  
 
-pub struct SwendsenWangAlgorithm
+// One Swendsen-Wang update, split in two like SwendsenWangAlgorithm:
+//      perform_swendsen_wang_all: activate the bonds & label the clusters, returns (energy, signed spin sum) of the configuration
+//      flip_cluster_and_take_fourier: flip the clusters & (optionally) return the Fourier amplitudes (sigma_q0, sigma_qx) after the flip
+// Implemented by SwendsenWangAlgorithm (sequential) and ParallelSwendsenWang (strips labelled in parallel)
+pub trait ClusterUpdate<S: SpinLattice>: Send
 {
-    labels: ClusterLabels,
-    eq_classes: EquivalenceClass,
-    cluster_flip_probabilities: Vec<f32>,
+    // builds the clusters of `spins`, returns its total energy & signed spin sum
+    fn perform_swendsen_wang_all<R: Rng + SeedableRng + Send>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64);
+    fn flip_cluster_and_take_fourier<R: Rng + SeedableRng + Send>(&mut self, spins: &mut S, rng: &mut R) -> (f64, Complex64);
+    fn reset(&mut self);
+    fn set_take_fourier_transform(&mut self, take_fourier_transform: bool);
+    fn take_fourier_transform(&self) -> bool;
+    fn set_bond_activation(&mut self, bond_activation: BondActivation);
+    // clusters of the last perform_swendsen_wang_all, before reset
+    fn cluster_map(&mut self, spins: &S) -> ClusterMap;
+}
+
+pub struct SwendsenWangAlgorithm<L: Label = usize>
+{
+    labels: ClusterLabels<L>,
+    eq_classes: EquivalenceClass<L>,
+    cluster_flips: Vec<Option<bool>>, // per cluster label (cluster_of): grows with the clusters, not with the lattice
+    labelling: LabellingStrategy,
+    labeller: Option<Box<dyn ClusterLabelling<L>>>, // None: single pass Hoshen-Kopelman (labels & eq_classes)
+    bonds: BondConfiguration,                        // only used with a labeller
+    site_clusters: Vec<L>,                           // only used with a labeller
+    pub bond_activation: BondActivation,
     pub take_fourier_transform: bool,
     fourier_kernels: Vec<Complex64> // [e^{iqx} for x in [0..Lx]] where q = 2\pi/Lx
 }
-impl SwendsenWangAlgorithm
+impl<L: Label> SwendsenWangAlgorithm<L>
 {
     pub fn new(rows: usize, cols: usize) -> Self
     {
-        let labels     = ClusterLabels::new(rows, cols);
-        let eq_classes = EquivalenceClass::new(rows*cols);
+        Self::with_union_find(rows, cols, UnionFindStrategy::default())
+    }
+    pub fn with_union_find(rows: usize, cols: usize, strategy: UnionFindStrategy) -> Self
+    {
+        Self::with_labelling(rows, cols, LabellingStrategy::default(), strategy)
+    }
+    pub fn with_labelling(rows: usize, cols: usize, labelling: LabellingStrategy, strategy: UnionFindStrategy) -> Self
+    {
+        assert!(rows*cols < L::MAX, "Lattice too large for the cluster label type");
+        let labeller = labelling.labeller(rows*cols, strategy);
+        let (labels, eq_classes, bonds, site_clusters) = match labeller
+        {
+            None    => (ClusterLabels::new(rows, cols), EquivalenceClass::with_strategy(rows*cols, strategy), BondConfiguration::new(0, 0), Vec::new()),
+            Some(_) => (ClusterLabels::new(0, 0), EquivalenceClass::new(0), BondConfiguration::new(rows, cols), vec![L::default(); rows*cols]),
+        };
 
-        let cluster_flip_probabilities: Vec<f32>   = vec![Default::default(); rows*cols];
+        let cluster_flips                          = Vec::new();
+        let bond_activation                        = BondActivation::default();
         let take_fourier_transform                 = false;
 
         let qx  = 2_f64 *PI / cols as f64;
         let fourier_kernels: Vec<Complex64> = (0..cols).map(|x|  (Complex64::i()* qx * (x as f64)).exp() ).collect();
 
-        Self { labels, eq_classes, cluster_flip_probabilities, take_fourier_transform, fourier_kernels}
+        Self { labels, eq_classes, cluster_flips, labelling, labeller, bonds, site_clusters, bond_activation, take_fourier_transform, fourier_kernels}
+    }
+    #[inline]
+    pub fn labelling(&self) -> LabellingStrategy
+    {
+        self.labelling
+    }
+    // Index of the cluster of `pos` after perform_swendsen_wang_all.
+    // Single pass Hoshen-Kopelman: the root label - 1, otherwise: the number of the cluster in order of first appearance
+    #[inline(always)]
+    pub fn cluster_of(&mut self, pos: (i32, i32)) -> usize
+    {
+        match self.labeller
+        {
+            None    => self.eq_classes.find(self.labels.at_pos(pos)) - 1,
+            Some(_) => self.site_clusters[pos.0 as usize * self.bonds.shape().1 + pos.1 as usize].to_usize(),
+        }
     }
     #[inline(always)]
-    fn reset_cluster_flip_probabilities(&mut self)
+    fn reset_cluster_flips(&mut self)
     {
-        self.cluster_flip_probabilities.fill(Default::default());
+        self.cluster_flips.clear();
     }
     #[inline(always)]
     fn merge_clusters_above(&mut self, pos: (i32, i32), above: (i32, i32))
@@ -116,7 +217,7 @@ impl SwendsenWangAlgorithm
         self.labels.set(pos, label);
     }
     #[inline(always)]
-    fn handle_top(&mut self, spins: &IsingArray2D, rng: &mut SmallRng, proba_add: f64) -> (f64, f64)
+    fn handle_top<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
     {
         let mut energy_total = 0_f64;
         let mut spin_sum     = 0_f64;
@@ -142,7 +243,7 @@ impl SwendsenWangAlgorithm
             let s         = spins.at_pos(pos);
             let left_spin = spins.at_pos(left); 
             
-            if s == left_spin && rng.random_bool(proba_add)  
+            if s == left_spin && sampler.activate(rng)  
             {
                 let left_label = self.labels.at_pos(left);
                 self.merge_clusters_left(pos, left_label);
@@ -161,7 +262,7 @@ impl SwendsenWangAlgorithm
         let s           = spins.at_pos(top_right);
         let left_spin   = spins.at_pos(left); 
 
-        if s == left_spin && rng.random_bool(proba_add)  
+        if s == left_spin && sampler.activate(rng)  
         {
             let left_label = self.labels.at_pos(left);
             self.merge_clusters_left(top_right, left_label);
@@ -172,7 +273,7 @@ impl SwendsenWangAlgorithm
         }
 
         // connect with top left corner:
-        if s == top_left_spin && rng.random_bool(proba_add)
+        if s == top_left_spin && sampler.activate(rng)
         {
             self.merge_clusters_pbc(top_right, top_left);
         }
@@ -183,7 +284,7 @@ impl SwendsenWangAlgorithm
         (energy_total, spin_sum)
     }
     #[inline(always)]
-    fn handle_rows(&mut self, spins: &IsingArray2D, rng: &mut SmallRng, proba_add: f64) -> (f64, f64)
+    fn handle_rows<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
     {
         let mut energy_total = 0_f64;
         let mut spin_sum     = 0_f64;
@@ -196,7 +297,7 @@ impl SwendsenWangAlgorithm
             let above          = left_edge.above(); 
             let left_edge_spin = spins.at_pos(left_edge);
             let above_spin     = spins.at_pos(above);
-            if left_edge_spin == above_spin && rng.random_bool(proba_add) 
+            if left_edge_spin == above_spin && sampler.activate(rng) 
             {
                 self.merge_clusters_above(left_edge, above);
             }
@@ -216,10 +317,10 @@ impl SwendsenWangAlgorithm
                 let s          = spins.at_pos(pos);
                 let left_spin  = spins.at_pos(left);  
                 let above_spin = spins.at_pos(above);  
-                if s == left_spin && rng.random_bool(proba_add)  
+                if s == left_spin && sampler.activate(rng)  
                 {
                     let left_label = self.labels.at_pos(left);
-                    if s == above_spin && rng.random_bool(proba_add)
+                    if s == above_spin && sampler.activate(rng)
                     {
                         self.merge_clusters_above_and_left(pos, above, left_label);
                     }
@@ -228,7 +329,7 @@ impl SwendsenWangAlgorithm
                         self.merge_clusters_left(pos, left_label);
                     }
                 }
-                else if s == above_spin && rng.random_bool(proba_add) 
+                else if s == above_spin && sampler.activate(rng) 
                 {
                     self.merge_clusters_above(pos, above);
                 }
@@ -247,10 +348,10 @@ impl SwendsenWangAlgorithm
             let s           = spins.at_pos(right_edge);
             let left_spin   = spins.at_pos(left);  
             let above_spin  = spins.at_pos(above);
-            if s == left_spin && rng.random_bool(proba_add)  
+            if s == left_spin && sampler.activate(rng)  
             {
                 let left_label = self.labels.at_pos(left);
-                if s == above_spin && rng.random_bool(proba_add)
+                if s == above_spin && sampler.activate(rng)
                 {
                     self.merge_clusters_above_and_left(right_edge, above, left_label);
                 }
@@ -259,7 +360,7 @@ impl SwendsenWangAlgorithm
                     self.merge_clusters_left(right_edge, left_label);
                 }
             }
-            else if s == above_spin && rng.random_bool(proba_add) 
+            else if s == above_spin && sampler.activate(rng) 
             {
                 self.merge_clusters_above(right_edge, above);
             }
@@ -269,7 +370,7 @@ impl SwendsenWangAlgorithm
             }
 
             // connect with left edge:
-            if s == left_edge_spin && rng.random_bool(proba_add)
+            if s == left_edge_spin && sampler.activate(rng)
             {
                 self.merge_clusters_pbc(right_edge, left_edge);
             }
@@ -280,7 +381,7 @@ impl SwendsenWangAlgorithm
         return (energy_total, spin_sum);
     }
     #[inline(always)]
-    fn handle_bottom(&mut self, spins: &IsingArray2D, rng: &mut SmallRng, proba_add: f64) -> (f64, f64)
+    fn handle_bottom<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
     {
         let mut energy_total = 0_f64;
         let mut spin_sum     = 0_f64;
@@ -294,7 +395,7 @@ impl SwendsenWangAlgorithm
         let above_spin       = spins.at_pos(above);
         let top_left_spin    = spins.at_pos(top_left);
 
-        if bottom_left_spin == above_spin && rng.random_bool(proba_add) 
+        if bottom_left_spin == above_spin && sampler.activate(rng) 
         {
             self.merge_clusters_above(bottom_left, above);
         }
@@ -303,7 +404,7 @@ impl SwendsenWangAlgorithm
             self.make_new_cluster(bottom_left);
         }
         // connect to top left
-        if bottom_left_spin == top_left_spin && rng.random_bool(proba_add)
+        if bottom_left_spin == top_left_spin && sampler.activate(rng)
         {
             self.merge_clusters_pbc(bottom_left, top_left);
         }
@@ -323,10 +424,10 @@ impl SwendsenWangAlgorithm
             let top_spin   = spins.at_pos(top);
             let above_spin = spins.at_pos(above);
 
-            if s == left_spin && rng.random_bool(proba_add)  
+            if s == left_spin && sampler.activate(rng)  
             {
                 let left_label = self.labels.at_pos(left);
-                if s == above_spin && rng.random_bool(proba_add)
+                if s == above_spin && sampler.activate(rng)
                 {
                     self.merge_clusters_above_and_left(pos, above, left_label);
                 }
@@ -335,7 +436,7 @@ impl SwendsenWangAlgorithm
                     self.merge_clusters_left(pos, left_label);
                 }
             }
-            else if s == above_spin && rng.random_bool(proba_add)
+            else if s == above_spin && sampler.activate(rng)
             {
                 self.merge_clusters_above(pos, above);
             }
@@ -344,7 +445,7 @@ impl SwendsenWangAlgorithm
                 self.make_new_cluster(pos);
             }
             // PBC:
-            if s == top_spin && rng.random_bool(proba_add)
+            if s == top_spin && sampler.activate(rng)
             {
                 self.merge_clusters_pbc(pos, top);
             }
@@ -363,10 +464,10 @@ impl SwendsenWangAlgorithm
         let top_right_spin = spins.at_pos(top_right);
         let above_spin     = spins.at_pos(above);
 
-        if s == left_spin && rng.random_bool(proba_add)  
+        if s == left_spin && sampler.activate(rng)  
         {
             let left_label = self.labels.at_pos(left);
-            if s == above_spin && rng.random_bool(proba_add)
+            if s == above_spin && sampler.activate(rng)
             {
                 self.merge_clusters_above_and_left(bottom_right, above, left_label);
             }
@@ -375,7 +476,7 @@ impl SwendsenWangAlgorithm
                 self.merge_clusters_left(bottom_right, left_label);
             }
         }
-        else if s == above_spin && rng.random_bool(proba_add)
+        else if s == above_spin && sampler.activate(rng)
         {
             self.merge_clusters_above(bottom_right, above);
         }
@@ -384,11 +485,11 @@ impl SwendsenWangAlgorithm
             self.make_new_cluster(bottom_right);
         }
         // connect with bottom left corner && top right corner:
-        if s == bottom_left_spin && rng.random_bool(proba_add)
+        if s == bottom_left_spin && sampler.activate(rng)
         {
             self.merge_clusters_pbc(bottom_right, bottom_left);
         }
-        if s == top_right_spin && rng.random_bool(proba_add)
+        if s == top_right_spin && sampler.activate(rng)
         {
             self.merge_clusters_pbc(bottom_right, top_right);
         }
@@ -399,19 +500,55 @@ impl SwendsenWangAlgorithm
         
         (energy_total, spin_sum)
     }
+    // builds the clusters of `spins`, returns its total energy & signed spin sum (take .abs() for <|m|>)
     #[inline(always)]
-    pub fn perform_swendsen_wang_all(&mut self, spins: &IsingArray2D, rng: &mut SmallRng, proba_add: f64) -> (f64, f64)
+    pub fn perform_swendsen_wang_all<S: SpinLattice, R: Rng + ?Sized>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64)
     {
-        let (dE1, dS1) = self.handle_top(spins, rng, proba_add);
-        let (dE2, dS2) = self.handle_rows(spins, rng, proba_add);
-        let (dE3, dS3) = self.handle_bottom(spins, rng, proba_add);
+        match self.bond_activation
+        {
+            BondActivation::PerBond       => self.perform_swendsen_wang_with(spins, rng, &mut PerBondSampler::new(proba_add)),
+            BondActivation::GeometricSkip =>
+            {
+                let mut sampler = GeometricSkipSampler::new(rng, proba_add);
+                self.perform_swendsen_wang_with(spins, rng, &mut sampler)
+            }
+        }
+    }
+    #[inline(always)]
+    fn perform_swendsen_wang_with<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
+    {
+        if let Some(labeller) = self.labeller.as_mut()
+        {
+            let (total_energy, spin_sum) = self.bonds.activate_with(spins, rng, sampler);
+            labeller.label_clusters(&self.bonds, &mut self.site_clusters);
+            return (total_energy, spin_sum);
+        }
+        let (dE1, dS1) = self.handle_top(spins, rng, sampler);
+        let (dE2, dS2) = self.handle_rows(spins, rng, sampler);
+        let (dE3, dS3) = self.handle_bottom(spins, rng, sampler);
 
-        let spin_sum_abs = (dS1+dS2+dS3).abs();
+        let spin_sum     = dS1+dS2+dS3; // signed: take .abs() for <|m|>
         let total_energy = dE1+dE2+dE3;
 
-        (total_energy, spin_sum_abs)
+        (total_energy, spin_sum)
     }    
-    pub fn flip_cluster_and_take_fourier(&mut self, spins: &mut IsingArray2D, rng: &mut SmallRng) -> (f64, Complex64)
+    // Clusters of the last perform_swendsen_wang_all (valid until reset)
+    pub fn cluster_map<S: SpinLattice>(&mut self, spins: &S) -> ClusterMap
+    {
+        let (Ly, Lx) = spins.shape();
+        let clusters: Vec<usize> = (0..Ly).flat_map(|y| (0..Lx).map(move |x| (y, x))).map(|pos| self.cluster_of(pos)).collect();
+        ClusterMap::from_cluster_indices(Ly as usize, Lx as usize, clusters)
+    }
+    // Only the Fortuin-Kasteleyn bond percolation step: the clusters are returned instead of being flipped,
+    // flipping is left to the caller (ClusterMap::flip_clusters).
+    pub fn bond_percolation<S: SpinLattice, R: Rng + ?Sized>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> ClusterMap
+    {
+        self.perform_swendsen_wang_all(spins, rng, proba_add);
+        let cluster_map = self.cluster_map(spins);
+        self.reset();
+        cluster_map
+    }
+    pub fn flip_cluster_and_take_fourier<S: SpinLattice, R: Rng + ?Sized>(&mut self, spins: &mut S, rng: &mut R) -> (f64, Complex64)
     {
         let p_flip      = 0.5_f32;
         let (Ly, Lx)    = spins.shape();
@@ -424,14 +561,13 @@ impl SwendsenWangAlgorithm
             for x in spins.columns()
             {
                 let pos           = (y,x);
-                let label         = self.labels.at_pos(pos);
-                let cluster_class = self.eq_classes.find(label)-1; 
+                let cluster_class = self.cluster_of(pos);
 
-                if self.cluster_flip_probabilities[cluster_class] == 0_f32
+                if cluster_class >= self.cluster_flips.len()
                 {
-                    self.cluster_flip_probabilities[cluster_class] = rng.random();
+                    self.cluster_flips.resize(cluster_class + 1, None);
                 }
-                if self.cluster_flip_probabilities[cluster_class] < p_flip
+                if *self.cluster_flips[cluster_class].get_or_insert_with(|| rng.random::<f32>() < p_flip)
                 {
                     spins.flip_at(y, x);
                 }
@@ -449,7 +585,7 @@ impl SwendsenWangAlgorithm
     }
     pub fn reset(&mut self)
     {
-        self.reset_cluster_flip_probabilities();
+        self.reset_cluster_flips();
         self.eq_classes.reset();
         self.labels.reset();
     }
@@ -462,10 +598,10 @@ impl SwendsenWangAlgorithm
     // This version was first adapted from my Python-Numba version. a bit less efficient but more readable!
     
     #[allow(dead_code)] 
-    fn perform_swendsen_wang_compact_version(&mut self, spins: &IsingArray2D, rng: &mut SmallRng, proba_add: f64) -> (f64, f64)
+    fn perform_swendsen_wang_compact_version<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
     {
         let mut energy_total = 0_f64;
-        let mut spin_sum     = 0_f64;
+        let mut spin_sum     = 0_f64; // signed, as in perform_swendsen_wang_all
         let (Ly, Lx) = spins.shape();
         
         for y in spins.rows()
@@ -487,10 +623,10 @@ impl SwendsenWangAlgorithm
                 let top_spin       = spins.at_pos(top);
                 let left_edge_spin = spins.at_pos(left_edge);
                 
-                if s == left_spin && rng.random_bool(proba_add) //should_add_to_cluster(rng, J_int, temp)
+                if s == left_spin && sampler.activate(rng) //should_add_to_cluster(rng, J_int, temp)
                 {
                     let left_label = self.labels.at_pos(left);
-                    if s == above_spin && rng.random_bool(proba_add)
+                    if s == above_spin && sampler.activate(rng)
                     {
                         self.merge_clusters_above_and_left(pos, above, left_label);
                     }
@@ -499,7 +635,7 @@ impl SwendsenWangAlgorithm
                         self.merge_clusters_left(pos, left_label);
                     }
                 }
-                else if s == above_spin && rng.random_bool(proba_add) 
+                else if s == above_spin && sampler.activate(rng) 
                 {
                     self.merge_clusters_above(pos, above);
                 }
@@ -509,11 +645,11 @@ impl SwendsenWangAlgorithm
                 }
 
                 // Connect bottom & top as well as right edge to left edge:
-                if pos == bottom && s == top_spin && rng.random_bool(proba_add)
+                if pos == bottom && s == top_spin && sampler.activate(rng)
                 {
                     self.merge_clusters_pbc(pos, top);
                 }
-                if pos == right_edge && s == left_edge_spin && rng.random_bool(proba_add)
+                if pos == right_edge && s == left_edge_spin && sampler.activate(rng)
                 {
                     self.merge_clusters_pbc(pos, left_edge);
                 }
@@ -523,7 +659,73 @@ impl SwendsenWangAlgorithm
                 spin_sum     += s as f64;
             }
         }
-        spin_sum = spin_sum.abs();
         return (energy_total, spin_sum)
     }
 }
+
+
+impl<S: SpinLattice, L: Label> ClusterUpdate<S> for SwendsenWangAlgorithm<L>
+{
+    #[inline(always)]
+    fn perform_swendsen_wang_all<R: Rng + SeedableRng + Send>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64)
+    {
+        SwendsenWangAlgorithm::perform_swendsen_wang_all(self, spins, rng, proba_add)
+    }
+    #[inline(always)]
+    fn flip_cluster_and_take_fourier<R: Rng + SeedableRng + Send>(&mut self, spins: &mut S, rng: &mut R) -> (f64, Complex64)
+    {
+        SwendsenWangAlgorithm::flip_cluster_and_take_fourier(self, spins, rng)
+    }
+    fn reset(&mut self)
+    {
+        SwendsenWangAlgorithm::reset(self)
+    }
+    fn set_take_fourier_transform(&mut self, take_fourier_transform: bool)
+    {
+        self.take_fourier_transform = take_fourier_transform;
+    }
+    fn take_fourier_transform(&self) -> bool
+    {
+        self.take_fourier_transform
+    }
+    fn set_bond_activation(&mut self, bond_activation: BondActivation)
+    {
+        self.bond_activation = bond_activation;
+    }
+    fn cluster_map(&mut self, spins: &S) -> ClusterMap
+    {
+        SwendsenWangAlgorithm::cluster_map(self, spins)
+    }
+}
+
+impl<S: SpinLattice, L: Label> ClusterUpdate<S> for ParallelSwendsenWang<L>
+{
+    fn perform_swendsen_wang_all<R: Rng + SeedableRng + Send>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64)
+    {
+        ParallelSwendsenWang::perform_swendsen_wang_all(self, spins, rng, proba_add)
+    }
+    fn flip_cluster_and_take_fourier<R: Rng + SeedableRng + Send>(&mut self, spins: &mut S, _rng: &mut R) -> (f64, Complex64)
+    {
+        ParallelSwendsenWang::flip_cluster_and_take_fourier(self, spins)
+    }
+    fn reset(&mut self)
+    {
+        // labels are re-initialised by every labelling pass
+    }
+    fn set_take_fourier_transform(&mut self, take_fourier_transform: bool)
+    {
+        self.take_fourier_transform = take_fourier_transform;
+    }
+    fn take_fourier_transform(&self) -> bool
+    {
+        self.take_fourier_transform
+    }
+    fn set_bond_activation(&mut self, bond_activation: BondActivation)
+    {
+        self.bond_activation = bond_activation;
+    }
+    fn cluster_map(&mut self, _spins: &S) -> ClusterMap
+    {
+        ParallelSwendsenWang::cluster_map(self)
+    }
+}
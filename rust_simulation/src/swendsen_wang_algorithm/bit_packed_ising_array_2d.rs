@@ -0,0 +1,91 @@
+use rayon::prelude::*;
+use super::ising_array_2d::IsingArray2D;
+use super::spin_lattice::SpinLattice;
+
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+// One bit per spin (set: +1, unset: -1), row-major like IsingArray2D.
+// 8 times less memory than IsingArray2D, for very large lattices (16384² spins: 32MB).
+pub struct BitPackedIsingArray2D
+{
+    data: Vec<u64>,
+    rows: i32,
+    cols: i32,
+}
+
+impl BitPackedIsingArray2D
+{
+    #[inline(always)]
+    fn word_and_bit(&self, i: i32, j: i32) -> (usize, u32)
+    {
+        let index = (i*self.cols) as usize + j as usize;
+        (index / BITS_PER_WORD, (index % BITS_PER_WORD) as u32)
+    }
+}
+
+impl SpinLattice for BitPackedIsingArray2D
+{
+    fn new_polarized(rows: usize, cols: usize) -> Self
+    {
+        let data = vec![u64::MAX; (rows*cols).div_ceil(BITS_PER_WORD)];
+        let rows = rows as i32;
+        let cols = cols as i32;
+
+        Self {data, rows, cols}
+    }
+    #[inline(always)]
+    fn at(&self, i: i32, j: i32) -> i8
+    {
+        let (word, bit) = self.word_and_bit(i, j);
+        2 * ((self.data[word] >> bit) & 1) as i8 - 1
+    }
+    #[inline(always)]
+    fn flip_at(&mut self, i: i32, j: i32)
+    {
+        let (word, bit) = self.word_and_bit(i, j);
+        self.data[word] ^= 1 << bit;
+    }
+    #[inline(always)]
+    fn shape(&self) -> (i32, i32)
+    {
+        (self.rows, self.cols)
+    }
+    fn par_flip_where<F: Fn(i32, i32) -> bool + Sync>(&mut self, should_flip: F)
+    {
+        let num_spins = (self.rows*self.cols) as usize;
+        let cols      = self.cols as usize;
+        self.data.par_iter_mut().enumerate().for_each(|(word_index, word)|
+        {
+            let first = word_index * BITS_PER_WORD;
+            let last  = num_spins.min(first + BITS_PER_WORD);
+            for index in first..last
+            {
+                if should_flip((index / cols) as i32, (index % cols) as i32)
+                {
+                    *word ^= 1 << (index - first);
+                }
+            }
+        });
+    }
+    fn to_ising_array(&self) -> IsingArray2D
+    {
+        let spins = self.rows().flat_map(|i| self.columns().map(move |j| self.at(i, j))).collect();
+        IsingArray2D::from_spins(self.rows as usize, self.cols as usize, spins).expect("spins are +-1")
+    }
+    fn from_ising_array(spins: IsingArray2D) -> Self
+    {
+        let (rows, cols) = spins.shape();
+        let mut packed   = Self::new_polarized(rows as usize, cols as usize);
+        for i in 0..rows
+        {
+            for j in 0..cols
+            {
+                if spins.at(i, j) < 0
+                {
+                    packed.flip_at(i, j);
+                }
+            }
+        }
+        packed
+    }
+}
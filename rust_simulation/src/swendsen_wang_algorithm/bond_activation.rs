@@ -0,0 +1,116 @@
+use rand::Rng;
+
+
+// How satisfied bonds (between equal spins) are activated:
+//      PerBond:       one rng.random_bool(proba_add) per satisfied bond
+//      GeometricSkip: only the rare outcome is sampled, the number of common outcomes in between being geometric:
+//                     below Tc nearly every bond is activated, so one random number is drawn per *inactive* bond.
+// Both give independent Bernoulli(proba_add) bonds, i.e. exactly the same statistics (but not the same random stream).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BondActivation
+{
+    #[default]
+    PerBond,
+    GeometricSkip,
+}
+
+impl BondActivation
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            BondActivation::PerBond       => "per_bond",
+            BondActivation::GeometricSkip => "geometric_skip",
+        }
+    }
+}
+
+impl std::str::FromStr for BondActivation
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "per_bond"                    => Ok(BondActivation::PerBond),
+            "geometric_skip" | "geometric" => Ok(BondActivation::GeometricSkip),
+            other => Err(format!("Unknown bond activation \"{other}\" (expected per_bond or geometric_skip)")),
+        }
+    }
+}
+
+
+// Decides, one satisfied bond after the other, whether it is activated
+pub trait BondSampler
+{
+    fn activate<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool;
+}
+
+pub struct PerBondSampler
+{
+    proba_add: f64,
+}
+
+impl PerBondSampler
+{
+    pub fn new(proba_add: f64) -> Self
+    {
+        Self { proba_add }
+    }
+}
+
+impl BondSampler for PerBondSampler
+{
+    #[inline(always)]
+    fn activate<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool
+    {
+        rng.random_bool(self.proba_add)
+    }
+}
+
+// The run of common outcomes before the next rare one has P(k) = (1-r)^k r, where r = min(p, 1-p):
+// it is sampled by inversion, k = floor(ln(U) / ln(1-r)) with U uniform in (0, 1].
+pub struct GeometricSkipSampler
+{
+    rare: bool,          // the least likely outcome: activated if proba_add < 1/2
+    log_common: f64,     // ln(1-r)
+    remaining: usize,    // common outcomes left before the next rare one
+}
+
+impl GeometricSkipSampler
+{
+    pub fn new<R: Rng + ?Sized>(rng: &mut R, proba_add: f64) -> Self
+    {
+        let rare          = proba_add < 0.5;
+        let proba_rare    = if rare { proba_add } else { 1_f64 - proba_add };
+        let mut sampler   = Self { rare, log_common: (-proba_rare).ln_1p(), remaining: 0 };
+        sampler.remaining = sampler.draw_run(rng);
+        sampler
+    }
+    #[inline(always)]
+    fn draw_run<R: Rng + ?Sized>(&self, rng: &mut R) -> usize
+    {
+        if self.log_common == 0_f64
+        {
+            return usize::MAX; // the rare outcome never happens
+        }
+        let u: f64 = 1_f64 - rng.random::<f64>();
+        (u.ln() / self.log_common).floor() as usize // saturating cast
+    }
+}
+
+impl BondSampler for GeometricSkipSampler
+{
+    #[inline(always)]
+    fn activate<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool
+    {
+        if self.remaining > 0
+        {
+            self.remaining -= 1;
+            return !self.rare;
+        }
+        self.remaining = self.draw_run(rng);
+        self.rare
+    }
+}
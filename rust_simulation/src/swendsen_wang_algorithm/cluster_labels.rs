@@ -1,17 +1,56 @@
-#[allow(dead_code)]
+// Integer type used to store cluster labels: usize (default) or u32 to halve the memory of large lattices.
+// Labels are handled as usize by the algorithm and only narrowed when stored.
+pub trait Label: Copy + Default + Send + Sync + 'static
+{
+    const MAX: usize;
+    fn from_usize(value: usize) -> Self;
+    fn to_usize(self) -> usize;
+}
+
+impl Label for usize
+{
+    const MAX: usize = usize::MAX;
+    #[inline(always)]
+    fn from_usize(value: usize) -> Self
+    {
+        value
+    }
+    #[inline(always)]
+    fn to_usize(self) -> usize
+    {
+        self
+    }
+}
 
-pub struct ClusterLabels
+impl Label for u32
 {
-    data: Vec<usize>,
+    const MAX: usize = u32::MAX as usize;
+    #[inline(always)]
+    fn from_usize(value: usize) -> Self
+    {
+        debug_assert!(value <= <Self as Label>::MAX, "label does not fit in u32");
+        value as u32
+    }
+    #[inline(always)]
+    fn to_usize(self) -> usize
+    {
+        self as usize
+    }
+}
+
+#[allow(dead_code)]
+pub struct ClusterLabels<L: Label = usize>
+{
+    data: Vec<L>,
     rows: usize,
     cols: usize,
 }
 
-impl ClusterLabels
+impl<L: Label> ClusterLabels<L>
 {
     pub fn new(rows: usize, cols: usize) -> Self
     {
-        let default_value = rows*cols;  
+        let default_value = L::from_usize(rows*cols);
         let data = vec![default_value; rows*cols];
         Self {data, rows, cols}
     }
@@ -20,18 +59,18 @@ impl ClusterLabels
     {
         let i = pos.0 as usize;
         let j = pos.1 as usize;
-        self.data[i*self.cols + j]
+        self.data[i*self.cols + j].to_usize()
     }
     #[inline]
     pub fn set(&mut self, pos: (i32, i32), label: usize)
     {
         let i = pos.0 as usize;
         let j = pos.1 as usize;
-        self.data[i*self.cols + j] = label;
+        self.data[i*self.cols + j] = L::from_usize(label);
     }
     #[inline]
     pub fn reset(&mut self)
     {
         self.data.fill(Default::default());
     }
-}
\ No newline at end of file
+}
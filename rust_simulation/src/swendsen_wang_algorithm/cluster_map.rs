@@ -0,0 +1,105 @@
+use super::spin_lattice::SpinLattice;
+
+
+// Fortuin-Kasteleyn clusters of one bond configuration, as returned by SwendsenWangAlgorithm::bond_percolation.
+// Clusters are labelled 0, 1, 2... in order of first appearance (row by row), so the label of a cluster is also
+// the label of its root, its first site. Nothing is flipped: see flip_clusters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterMap
+{
+    labels: Vec<usize>, // cluster label of every site, row major
+    sizes: Vec<usize>,  // number of sites of every cluster
+    rows: usize,
+    cols: usize,
+}
+
+impl ClusterMap
+{
+    // `clusters` gives any cluster index < rows*cols for every site (row major), they are renumbered in order of first appearance
+    pub fn from_cluster_indices<I: IntoIterator<Item = usize>>(rows: usize, cols: usize, clusters: I) -> Self
+    {
+        let unset          = usize::MAX;
+        let mut renumbered = vec![unset; rows*cols];
+        let mut sizes      = Vec::new();
+        let labels: Vec<usize> = clusters.into_iter().map(|cluster|
+        {
+            if renumbered[cluster] == unset
+            {
+                renumbered[cluster] = sizes.len();
+                sizes.push(0);
+            }
+            let label     = renumbered[cluster];
+            sizes[label] += 1;
+            label
+        }).collect();
+        assert_eq!(labels.len(), rows*cols, "one cluster index per site is needed");
+
+        Self { labels, sizes, rows, cols }
+    }
+    #[inline]
+    pub fn shape(&self) -> (usize, usize)
+    {
+        (self.rows, self.cols)
+    }
+    #[inline]
+    pub fn num_clusters(&self) -> usize
+    {
+        self.sizes.len()
+    }
+    #[inline]
+    pub fn label_at(&self, i: i32, j: i32) -> usize
+    {
+        self.labels[i as usize * self.cols + j as usize]
+    }
+    #[inline]
+    pub fn labels(&self) -> &[usize]
+    {
+        &self.labels
+    }
+    #[inline]
+    pub fn sizes(&self) -> &[usize]
+    {
+        &self.sizes
+    }
+    #[inline]
+    pub fn size_of(&self, label: usize) -> usize
+    {
+        self.sizes[label]
+    }
+    // label of the (first) largest cluster
+    pub fn largest_cluster(&self) -> Option<usize>
+    {
+        self.sizes.iter().enumerate().rev().max_by_key(|&(_, &size)| size).map(|(label, _)| label)
+    }
+    pub fn sites_of(&self, label: usize) -> impl Iterator<Item = (i32, i32)> + '_
+    {
+        let cols = self.cols;
+        self.labels.iter().enumerate()
+            .filter(move |&(_, &site_label)| site_label == label)
+            .map(move |(site, _)| ((site / cols) as i32, (site % cols) as i32))
+    }
+
+    // Flips every cluster for which should_flip(label, size) is true.
+    // The rule is called once per cluster, in label order: e.g. |_, _| rng.random_bool(0.5) for Swendsen-Wang,
+    // or |label, _| label == map.label_at(y, x) to only flip the cluster of one site (Wolff).
+    // Returns the number of flipped spins.
+    pub fn flip_clusters<S: SpinLattice, F: FnMut(usize, usize) -> bool>(&self, spins: &mut S, mut should_flip: F) -> usize
+    {
+        assert_eq!(spins.shape(), (self.rows as i32, self.cols as i32), "cluster map & spins have different shapes");
+        let flip: Vec<bool> = self.sizes.iter().enumerate().map(|(label, &size)| should_flip(label, size)).collect();
+
+        let mut flipped = 0;
+        for i in spins.rows()
+        {
+            for j in spins.columns()
+            {
+                if flip[self.label_at(i, j)]
+                {
+                    spins.flip_at(i, j);
+                    flipped += 1;
+                }
+            }
+        }
+        flipped
+    }
+}
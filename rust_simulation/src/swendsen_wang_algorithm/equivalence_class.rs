@@ -1,47 +1,137 @@
+use super::cluster_labels::Label;
 
-pub struct EquivalenceClass
+// How equivalence classes are merged:
+//      SmallestLabel: no path compression, the larger root is linked under the smaller one (the original Hoshen-Kopelman rule)
+//      BySize:        path halving in find, the smaller class is linked under the larger one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnionFindStrategy
 {
-    data: Vec<usize>,
+    #[default]
+    SmallestLabel,
+    BySize,
 }
-impl EquivalenceClass
+
+impl UnionFindStrategy
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            UnionFindStrategy::SmallestLabel => "smallest_label",
+            UnionFindStrategy::BySize        => "by_size",
+        }
+    }
+}
+
+impl std::str::FromStr for UnionFindStrategy
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "smallest_label" => Ok(UnionFindStrategy::SmallestLabel),
+            "by_size"        => Ok(UnionFindStrategy::BySize),
+            other => Err(format!("Unknown union-find strategy \"{other}\" (expected smallest_label or by_size)")),
+        }
+    }
+}
+
+pub struct EquivalenceClass<L: Label = usize>
+{
+    data: Vec<L>,
+    sizes: Vec<L>, // only used by UnionFindStrategy::BySize
+    strategy: UnionFindStrategy,
+}
+impl<L: Label> EquivalenceClass<L>
 {
     pub fn new(num_spins: usize) -> Self
     {
-        let data: Vec<usize> = (0..(num_spins+1)).collect();
-        Self { data}//, n_classes, }
+        Self::with_strategy(num_spins, UnionFindStrategy::default())
+    }
+    pub fn with_strategy(num_spins: usize, strategy: UnionFindStrategy) -> Self
+    {
+        let data: Vec<L> = (0..(num_spins+1)).map(L::from_usize).collect();
+        let sizes        = match strategy
+        {
+            UnionFindStrategy::SmallestLabel => Vec::new(),
+            UnionFindStrategy::BySize        => vec![L::from_usize(1); num_spins+1],
+        };
+        Self { data, sizes, strategy }
     }   
     #[inline]
+    pub fn strategy(&self) -> UnionFindStrategy
+    {
+        self.strategy
+    }
+    #[inline]
     pub fn reset(&mut self)
     {
-        self.data.iter_mut().enumerate().for_each(|(count, value)| *value = count);
+        self.data.iter_mut().enumerate().for_each(|(count, value)| *value = L::from_usize(count));
     }
     pub fn create_class(&mut self) -> usize
     {       
-        self.data[0]         += 1;
-        let new_cluster_index = self.data[0];
+        let new_cluster_index = self.data[0].to_usize() + 1;
+        self.data[0]          = L::from_usize(new_cluster_index);
         
-        self.data[new_cluster_index] = new_cluster_index;
-        self.data[0]
+        self.data[new_cluster_index] = L::from_usize(new_cluster_index);
+        if self.strategy == UnionFindStrategy::BySize
+        {
+            self.sizes[new_cluster_index] = L::from_usize(1);
+        }
+        new_cluster_index
 
     }
-    pub fn find(&self, mut cluster_result: usize) -> usize 
+    #[inline]
+    pub fn find(&mut self, mut cluster_result: usize) -> usize 
     {
-        while self.data[cluster_result] != cluster_result
+        match self.strategy
         {
-            cluster_result = self.data[cluster_result]
-        };
-        return cluster_result;
+            UnionFindStrategy::SmallestLabel =>
+            {
+                while self.data[cluster_result].to_usize() != cluster_result
+                {
+                    cluster_result = self.data[cluster_result].to_usize()
+                };
+            }
+            UnionFindStrategy::BySize =>
+            {
+                // path halving: every visited label ends up pointing to its grand parent
+                while self.data[cluster_result].to_usize() != cluster_result
+                {
+                    let parent                = self.data[cluster_result].to_usize();
+                    self.data[cluster_result] = self.data[parent];
+                    cluster_result            = self.data[parent].to_usize();
+                };
+            }
+        }
+        cluster_result
     }
     pub fn union_get_label(&mut self, cluster_label1: usize, cluster_label2: usize) -> usize
     {
-        let l1    = self.find(cluster_label1);
-        let l2    = self.find(cluster_label2);
-        let l_max = l1.max(l2);
-        let l_min = l1.min(l2);
-        if l_min != l_max
+        let l1 = self.find(cluster_label1);
+        let l2 = self.find(cluster_label2);
+        if l1 == l2
+        {
+            return l1;
+        }
+        match self.strategy
         {
-            self.data[l_max] = l_min;
+            UnionFindStrategy::SmallestLabel =>
+            {
+                let l_max = l1.max(l2);
+                let l_min = l1.min(l2);
+                self.data[l_max] = L::from_usize(l_min);
+                l_min
+            }
+            UnionFindStrategy::BySize =>
+            {
+                let (size1, size2)   = (self.sizes[l1].to_usize(), self.sizes[l2].to_usize());
+                let (large, small)   = if size1 >= size2 { (l1, l2) } else { (l2, l1) };
+                self.data[small]     = L::from_usize(large);
+                self.sizes[large]    = L::from_usize(size1 + size2);
+                large
+            }
         }
-        return l_min;
     }
 }
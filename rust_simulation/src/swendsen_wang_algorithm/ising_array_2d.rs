@@ -1,7 +1,9 @@
 #![allow(non_snake_case)]
-use rand::rngs::SmallRng;
 use rand::Rng;
+use rayon::prelude::*;
+use super::spin_lattice::SpinLattice;
 
+#[derive(Clone, PartialEq)]
 pub struct IsingArray2D
 {
     data: Vec<i8>,
@@ -10,7 +12,7 @@ pub struct IsingArray2D
 }
 
 
-trait MonteCarloModulo 
+pub(super) trait MonteCarloModulo 
 {
     fn modulo(self, other: Self) -> Self;   
 }
@@ -44,12 +46,31 @@ impl IsingArray2D
 
         Self {data, rows, cols}
     }
-    pub fn new_randomized(rng: &mut SmallRng, rows: usize, cols: usize) -> Self
+    pub fn new_randomized<R: Rng + ?Sized>(rng: &mut R, rows: usize, cols: usize) -> Self
     {
         let mut spins = IsingArray2D::new_polarized(rows, cols);
         spins.randomize_spins(rng);
         spins
     }
+    pub fn from_spins(rows: usize, cols: usize, data: Vec<i8>) -> Result<Self, &'static str>
+    {
+        if data.len() != rows*cols
+        {
+            return Err("IsingArray2D Error: data length should be rows*cols");
+        }
+        if data.iter().any(|&s| s != 1 && s != -1)
+        {
+            return Err("IsingArray2D Error: spins should be +1 or -1");
+        }
+        let rows = rows as i32;
+        let cols = cols as i32;
+        Ok(Self {data, rows, cols})
+    }
+    #[inline(always)]
+    pub fn as_slice(&self) -> &[i8]
+    {
+        &self.data
+    }
     #[inline(always)]
     pub fn at_pos(&self, pos: (i32, i32)) -> i8 
     {
@@ -113,11 +134,11 @@ impl IsingArray2D
     {
         0..self.cols
     }
-    pub fn randomize_spins(&mut self, rng: &mut SmallRng)
+    pub fn randomize_spins<R: Rng + ?Sized>(&mut self, rng: &mut R)
     {
-        for i in 0..self.cols
+        for i in 0..self.rows
         {
-            for j in 0..self.rows
+            for j in 0..self.cols
             {
                 if rng.random_bool(0.5)
                 {
@@ -128,6 +149,52 @@ impl IsingArray2D
     }
 }
 
+impl SpinLattice for IsingArray2D
+{
+    fn new_polarized(rows: usize, cols: usize) -> Self
+    {
+        IsingArray2D::new_polarized(rows, cols)
+    }
+    #[inline(always)]
+    fn at(&self, i: i32, j: i32) -> i8
+    {
+        self.at(i, j)
+    }
+    #[inline(always)]
+    fn at_periodic(&self, i: i32, j: i32) -> i8
+    {
+        self.at_periodic(i, j)
+    }
+    #[inline(always)]
+    fn flip_at(&mut self, i: i32, j: i32)
+    {
+        self.flip_at(i, j)
+    }
+    #[inline(always)]
+    fn shape(&self) -> (i32, i32)
+    {
+        self.shape()
+    }
+    fn par_flip_where<F: Fn(i32, i32) -> bool + Sync>(&mut self, should_flip: F)
+    {
+        self.data.par_chunks_mut(self.cols as usize).enumerate().for_each(|(i, row)|
+        {
+            row.iter_mut().enumerate()
+                .filter(|&(j, _)| should_flip(i as i32, j as i32))
+                .for_each(|(_, s)| *s *= -1);
+        });
+    }
+    fn to_ising_array(&self) -> IsingArray2D
+    {
+        let (rows, cols) = self.shape();
+        IsingArray2D { data: self.data.clone(), rows, cols }
+    }
+    fn from_ising_array(spins: IsingArray2D) -> Self
+    {
+        spins
+    }
+}
+
 use std::fmt;
 impl fmt::Debug for IsingArray2D
 {
@@ -0,0 +1,355 @@
+use rand::Rng;
+
+use super::cluster_labels::Label;
+use super::equivalence_class::{EquivalenceClass, UnionFindStrategy};
+use super::spin_lattice::SpinLattice;
+use super::bond_activation::{BondSampler, PerBondSampler};
+use super::J;
+
+
+// How clusters are identified:
+//      HoshenKopelman:   bonds activated & labelled in a single pass (SwendsenWangAlgorithm::handle_rows & co)
+//      UnionFind:        bonds activated first, then labelled by union-find over the sites (Hoshen-Kopelman on the bond configuration)
+//      FloodFill:        bonds activated first, then every cluster is grown from its first site with a stack
+//      LabelEquivalence: bonds activated first, then labels are propagated to the smallest site index until nothing changes
+// All of them draw the bonds in the same order and number the clusters in order of first appearance,
+// so they produce the very same Markov chain for a given seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LabellingStrategy
+{
+    #[default]
+    HoshenKopelman,
+    UnionFind,
+    FloodFill,
+    LabelEquivalence,
+}
+
+impl LabellingStrategy
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            LabellingStrategy::HoshenKopelman   => "hoshen_kopelman",
+            LabellingStrategy::UnionFind        => "union_find",
+            LabellingStrategy::FloodFill        => "flood_fill",
+            LabellingStrategy::LabelEquivalence => "label_equivalence",
+        }
+    }
+    // None for the single pass Hoshen-Kopelman labelling, which does not go through a BondConfiguration
+    pub fn labeller<L: Label>(&self, num_sites: usize, union_find: UnionFindStrategy) -> Option<Box<dyn ClusterLabelling<L>>>
+    {
+        match self
+        {
+            LabellingStrategy::HoshenKopelman   => None,
+            LabellingStrategy::UnionFind        => Some(Box::new(UnionFindLabelling::new(num_sites, union_find))),
+            LabellingStrategy::FloodFill        => Some(Box::new(FloodFill::new(num_sites))),
+            LabellingStrategy::LabelEquivalence => Some(Box::new(LabelEquivalence::new(num_sites))),
+        }
+    }
+}
+
+impl std::str::FromStr for LabellingStrategy
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "hoshen_kopelman" | "hk" => Ok(LabellingStrategy::HoshenKopelman),
+            "union_find"             => Ok(LabellingStrategy::UnionFind),
+            "flood_fill" | "bfs"     => Ok(LabellingStrategy::FloodFill),
+            "label_equivalence"      => Ok(LabellingStrategy::LabelEquivalence),
+            other => Err(format!("Unknown labelling strategy \"{other}\" (expected hoshen_kopelman, union_find, flood_fill or label_equivalence)")),
+        }
+    }
+}
+
+
+const RIGHT: u8 = 0b01;
+const DOWN: u8  = 0b10;
+
+// Activated Fortuin-Kasteleyn bonds of a periodic lattice: every site owns the bond to its right & the one below it
+// (the last column/row own the periodic bonds to the first column/row).
+pub struct BondConfiguration
+{
+    bonds: Vec<u8>,
+    rows: usize,
+    cols: usize,
+}
+
+impl BondConfiguration
+{
+    pub fn new(rows: usize, cols: usize) -> Self
+    {
+        Self { bonds: vec![0; rows*cols], rows, cols }
+    }
+    #[inline]
+    pub fn shape(&self) -> (usize, usize)
+    {
+        (self.rows, self.cols)
+    }
+    #[inline]
+    pub fn num_sites(&self) -> usize
+    {
+        self.bonds.len()
+    }
+    #[inline(always)]
+    pub fn right(&self, site: usize) -> bool
+    {
+        self.bonds[site] & RIGHT != 0
+    }
+    #[inline(always)]
+    pub fn down(&self, site: usize) -> bool
+    {
+        self.bonds[site] & DOWN != 0
+    }
+    #[inline]
+    pub fn set_right(&mut self, site: usize, active: bool)
+    {
+        self.bonds[site] = if active { self.bonds[site] | RIGHT } else { self.bonds[site] & !RIGHT };
+    }
+    #[inline]
+    pub fn set_down(&mut self, site: usize, active: bool)
+    {
+        self.bonds[site] = if active { self.bonds[site] | DOWN } else { self.bonds[site] & !DOWN };
+    }
+    #[inline(always)]
+    pub fn bonded_neighbours(&self, site: usize) -> impl Iterator<Item = usize> + '_
+    {
+        let (Ly, Lx) = (self.rows, self.cols);
+        let (y, x)   = (site / Lx, site % Lx);
+        let left     = y*Lx + (x + Lx - 1) % Lx;
+        let right    = y*Lx + (x + 1) % Lx;
+        let above    = ((y + Ly - 1) % Ly)*Lx + x;
+        let below    = ((y + 1) % Ly)*Lx + x;
+
+        [(self.right(site), right), (self.down(site), below), (self.right(left), left), (self.down(above), above)]
+            .into_iter()
+            .filter_map(|(bonded, ngbr)| bonded.then_some(ngbr))
+    }
+
+    // Activates every bond between equal spins with probability proba_add, returns (energy, signed spin sum).
+    // The random numbers are drawn in the order of the single pass Hoshen-Kopelman labelling:
+    // for each site, the bond to the left, above, and then the periodic bonds of the last column & last row.
+    pub fn activate<S: SpinLattice, R: Rng + ?Sized>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64)
+    {
+        self.activate_with(spins, rng, &mut PerBondSampler::new(proba_add))
+    }
+    pub fn activate_with<S: SpinLattice, R: Rng + ?Sized, B: BondSampler>(&mut self, spins: &S, rng: &mut R, sampler: &mut B) -> (f64, f64)
+    {
+        let (Ly, Lx)     = spins.shape();
+        let mut energy   = 0_f64;
+        let mut spin_sum = 0_f64;
+        self.bonds.fill(0);
+
+        for y in 0..Ly
+        {
+            for x in 0..Lx
+            {
+                let site = (y*Lx + x) as usize;
+                let s    = spins.at(y, x);
+
+                if x > 0 && s == spins.at(y, x-1) && sampler.activate(rng)
+                {
+                    self.bonds[site - 1] |= RIGHT;
+                }
+                if y > 0 && s == spins.at(y-1, x) && sampler.activate(rng)
+                {
+                    self.bonds[site - Lx as usize] |= DOWN;
+                }
+                if x == Lx-1 && s == spins.at(y, 0) && sampler.activate(rng)
+                {
+                    self.bonds[site] |= RIGHT;
+                }
+                if y == Ly-1 && s == spins.at(0, x) && sampler.activate(rng)
+                {
+                    self.bonds[site] |= DOWN;
+                }
+                energy   += -J*(s * spins.get_ngbrs_spin_sum_pbc((y, x))) as f64;
+                spin_sum += s as f64;
+            }
+        }
+        (energy, spin_sum)
+    }
+}
+
+
+// Cluster identification on a bond configuration.
+// Writes the cluster index of every site in `clusters`, clusters being numbered 0, 1, 2... in order of first appearance
+// (row by row), and returns the number of clusters.
+pub trait ClusterLabelling<L: Label>: Send
+{
+    fn label_clusters(&mut self, bonds: &BondConfiguration, clusters: &mut [L]) -> usize;
+}
+
+
+// Hoshen-Kopelman on the bond configuration: each site starts as its own class (site + 1)
+pub struct UnionFindLabelling<L: Label = usize>
+{
+    eq_classes: EquivalenceClass<L>,
+    cluster_of_root: Vec<usize>,
+}
+
+impl<L: Label> UnionFindLabelling<L>
+{
+    pub fn new(num_sites: usize, strategy: UnionFindStrategy) -> Self
+    {
+        Self { eq_classes: EquivalenceClass::with_strategy(num_sites, strategy), cluster_of_root: vec![0; num_sites] }
+    }
+}
+
+impl<L: Label> ClusterLabelling<L> for UnionFindLabelling<L>
+{
+    fn label_clusters(&mut self, bonds: &BondConfiguration, clusters: &mut [L]) -> usize
+    {
+        let num_sites = bonds.num_sites();
+        let (_, Lx)   = bonds.shape();
+        self.eq_classes.reset();
+        (0..num_sites).for_each(|_| { self.eq_classes.create_class(); });
+
+        for site in 0..num_sites
+        {
+            if bonds.right(site)
+            {
+                let right = site - site % Lx + (site + 1) % Lx;
+                self.eq_classes.union_get_label(site + 1, right + 1);
+            }
+            if bonds.down(site)
+            {
+                let below = (site + Lx) % num_sites;
+                self.eq_classes.union_get_label(site + 1, below + 1);
+            }
+        }
+
+        let unset = num_sites;
+        self.cluster_of_root.fill(unset);
+        let mut num_clusters = 0;
+        for (site, cluster) in clusters.iter_mut().enumerate()
+        {
+            let root = self.eq_classes.find(site + 1) - 1;
+            if self.cluster_of_root[root] == unset
+            {
+                self.cluster_of_root[root] = num_clusters;
+                num_clusters += 1;
+            }
+            *cluster = L::from_usize(self.cluster_of_root[root]);
+        }
+        num_clusters
+    }
+}
+
+
+// Depth first flood fill: clusters are grown one at a time, from their first site
+pub struct FloodFill
+{
+    stack: Vec<usize>,
+}
+
+impl FloodFill
+{
+    pub fn new(num_sites: usize) -> Self
+    {
+        Self { stack: Vec::with_capacity(num_sites) }
+    }
+}
+
+impl<L: Label> ClusterLabelling<L> for FloodFill
+{
+    fn label_clusters(&mut self, bonds: &BondConfiguration, clusters: &mut [L]) -> usize
+    {
+        let unset = bonds.num_sites();
+        clusters.fill(L::from_usize(unset));
+
+        let mut num_clusters = 0;
+        for start in 0..clusters.len()
+        {
+            if clusters[start].to_usize() != unset
+            {
+                continue;
+            }
+            let cluster     = L::from_usize(num_clusters);
+            clusters[start] = cluster;
+            self.stack.push(start);
+            while let Some(site) = self.stack.pop()
+            {
+                for ngbr in bonds.bonded_neighbours(site)
+                {
+                    if clusters[ngbr].to_usize() == unset
+                    {
+                        clusters[ngbr] = cluster;
+                        self.stack.push(ngbr);
+                    }
+                }
+            }
+            num_clusters += 1;
+        }
+        num_clusters
+    }
+}
+
+
+// Iterative label equivalence (as used on GPUs, Hawick, Leist & Playne 2010):
+// every site starts with its own index as label, then
+//      scanning:  the root of each label is lowered to the smallest label among the bonded neighbours
+//      analysis:  every label is replaced by its root
+// until no label changes. Each cluster ends up labelled by its smallest site index.
+pub struct LabelEquivalence
+{
+    labels: Vec<usize>,
+}
+
+impl LabelEquivalence
+{
+    pub fn new(num_sites: usize) -> Self
+    {
+        Self { labels: vec![0; num_sites] }
+    }
+}
+
+impl<L: Label> ClusterLabelling<L> for LabelEquivalence
+{
+    fn label_clusters(&mut self, bonds: &BondConfiguration, clusters: &mut [L]) -> usize
+    {
+        let labels = &mut self.labels;
+        labels.iter_mut().enumerate().for_each(|(site, label)| *label = site);
+
+        let mut changed = true;
+        while changed
+        {
+            changed = false;
+            for site in 0..labels.len()
+            {
+                let label    = labels[site];
+                let smallest = bonds.bonded_neighbours(site).map(|ngbr| labels[ngbr]).min().unwrap_or(label);
+                if smallest < label
+                {
+                    labels[label] = labels[label].min(smallest);
+                    changed       = true;
+                }
+            }
+            for site in 0..labels.len()
+            {
+                let mut root = labels[site];
+                while labels[root] != root
+                {
+                    root = labels[root];
+                }
+                labels[site] = root;
+            }
+        }
+
+        // the root of a cluster is its first site: it is reached before any other site of the cluster
+        let mut num_clusters = 0;
+        for site in 0..labels.len()
+        {
+            let root       = labels[site];
+            clusters[site] = match root == site
+            {
+                true  => { num_clusters += 1; L::from_usize(num_clusters - 1) }
+                false => clusters[root],
+            };
+        }
+        num_clusters
+    }
+}
@@ -0,0 +1,228 @@
+use num::complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::f64::consts::PI;
+
+use super::cluster_labels::Label;
+use super::spin_lattice::SpinLattice;
+use super::cluster_map::ClusterMap;
+use super::bond_activation::{BondActivation, BondSampler, PerBondSampler, GeometricSkipSampler};
+use super::J;
+use crate::random::splitmix64;
+
+
+// Domain decomposed Swendsen-Wang: the lattice is cut into strips of rows which are labelled in parallel,
+// each with its own union-find & random stream. The bonds between strips (including the periodic seam
+// between the last & first row) are sampled by the strip above them and stitched together afterwards.
+// Sites are labelled by their index, so a strip only ever touches its own part of `parent` until the stitching.
+//
+// The random numbers only depend on the number of strips (not on the number of threads):
+// the per-strip seeds & the cluster flip seed are drawn from the driver's generator at every sweep,
+// and clusters are flipped according to a hash of (flip seed, root).
+pub struct ParallelSwendsenWang<L: Label = usize>
+{
+    parent: Vec<L>,
+    boundary_bonds: Vec<Vec<(usize, usize)>>, // per strip: activated bonds between its last row & the next strip
+    rows: usize,
+    cols: usize,
+    strip_rows: usize,
+    flip_seed: u64,
+    pub bond_activation: BondActivation,
+    pub take_fourier_transform: bool,
+    fourier_kernels: Vec<Complex64> // [e^{iqx} for x in [0..Lx]] where q = 2\pi/Lx
+}
+
+// Path halving: every visited site ends up pointing to its grand parent.
+// `offset` is the index of parent[0] (strips only hold their part of the labels)
+#[inline(always)]
+fn find<L: Label>(parent: &mut [L], offset: usize, mut site: usize) -> usize
+{
+    loop
+    {
+        let up = parent[site - offset].to_usize();
+        if up == site
+        {
+            return site;
+        }
+        let grand_parent     = parent[up - offset];
+        parent[site - offset] = grand_parent;
+        site                  = grand_parent.to_usize();
+    }
+}
+
+#[inline(always)]
+fn find_readonly<L: Label>(parent: &[L], mut site: usize) -> usize
+{
+    while parent[site].to_usize() != site
+    {
+        site = parent[site].to_usize();
+    }
+    site
+}
+
+// the smallest root wins, as in EquivalenceClass::union_get_label
+#[inline(always)]
+fn union<L: Label>(parent: &mut [L], offset: usize, site1: usize, site2: usize)
+{
+    let root1 = find(parent, offset, site1);
+    let root2 = find(parent, offset, site2);
+    if root1 != root2
+    {
+        parent[root1.max(root2) - offset] = L::from_usize(root1.min(root2));
+    }
+}
+
+#[inline(always)]
+fn should_flip(flip_seed: u64, root: usize) -> bool
+{
+    splitmix64(flip_seed ^ splitmix64(root as u64)) >> 63 == 1
+}
+
+impl<L: Label> ParallelSwendsenWang<L>
+{
+    pub fn new(rows: usize, cols: usize, num_strips: usize) -> Self
+    {
+        assert!(rows*cols < L::MAX, "Lattice too large for the cluster label type");
+        let num_strips = num_strips.clamp(1, rows);
+        let strip_rows = rows.div_ceil(num_strips);
+        let num_strips = rows.div_ceil(strip_rows);
+
+        let parent         = vec![L::default(); rows*cols];
+        let boundary_bonds = vec![Vec::with_capacity(cols); num_strips];
+
+        let qx  = 2_f64 *PI / cols as f64;
+        let fourier_kernels: Vec<Complex64> = (0..cols).map(|x|  (Complex64::i()* qx * (x as f64)).exp() ).collect();
+
+        Self { parent, boundary_bonds, rows, cols, strip_rows, flip_seed: 0, bond_activation: BondActivation::default(), take_fourier_transform: false, fourier_kernels }
+    }
+    pub fn num_strips(&self) -> usize
+    {
+        self.boundary_bonds.len()
+    }
+
+    // Bonds to the left & above inside the strip, the periodic bond of each row,
+    // and the bonds from the last row of the strip to the row below (recorded in boundary_bonds).
+    // Returns the (energy, spin sum) of the strip.
+    fn label_strip<S: SpinLattice, R: Rng, B: BondSampler>(spins: &S, parent: &mut [L], boundary_bonds: &mut Vec<(usize, usize)>, first_row: usize, rng: &mut R, sampler: &mut B) -> (f64, f64)
+    {
+        let (Ly, Lx)     = spins.shape();
+        let offset       = first_row * Lx as usize;
+        let last_row     = first_row + parent.len() / Lx as usize - 1;
+        let mut energy   = 0_f64;
+        let mut spin_sum = 0_f64;
+
+        parent.iter_mut().enumerate().for_each(|(index, label)| *label = L::from_usize(offset + index));
+        boundary_bonds.clear();
+
+        for y in first_row as i32..=last_row as i32
+        {
+            for x in 0..Lx
+            {
+                let site = (y*Lx + x) as usize;
+                let s    = spins.at(y, x);
+
+                if x > 0 && s == spins.at(y, x-1) && sampler.activate(rng)
+                {
+                    union(parent, offset, site, site-1);
+                }
+                if y > first_row as i32 && s == spins.at(y-1, x) && sampler.activate(rng)
+                {
+                    union(parent, offset, site, site - Lx as usize);
+                }
+                if x == Lx-1 && s == spins.at(y, 0) && sampler.activate(rng)
+                {
+                    union(parent, offset, site, (y*Lx) as usize);
+                }
+                if y == last_row as i32
+                {
+                    let below = (y+1) % Ly;
+                    if s == spins.at(below, x) && sampler.activate(rng)
+                    {
+                        boundary_bonds.push((site, (below*Lx + x) as usize));
+                    }
+                }
+                energy   += -J*(s * spins.get_ngbrs_spin_sum_pbc((y, x))) as f64;
+                spin_sum += s as f64;
+            }
+        }
+        // one hop to the strip root for every site: the stitched roots are then only a few hops away
+        for index in 0..parent.len()
+        {
+            let root = find(parent, offset, offset + index);
+            parent[index] = L::from_usize(root);
+        }
+        (energy, spin_sum)
+    }
+
+    // as SwendsenWangAlgorithm::perform_swendsen_wang_all: (total energy, signed spin sum)
+    pub fn perform_swendsen_wang_all<S: SpinLattice, R: Rng + SeedableRng + Send>(&mut self, spins: &S, rng: &mut R, proba_add: f64) -> (f64, f64)
+    {
+        let strip_seeds: Vec<u64> = (0..self.num_strips()).map(|_| rng.random()).collect();
+        self.flip_seed = rng.random();
+
+        let strip_size = self.strip_rows * self.cols;
+        let strip_rows      = self.strip_rows;
+        let bond_activation = self.bond_activation;
+        let (energy, spin_sum) = self.parent.par_chunks_mut(strip_size)
+            .zip(self.boundary_bonds.par_iter_mut())
+            .zip(strip_seeds.par_iter())
+            .enumerate()
+            .map(|(strip, ((parent, boundary_bonds), &seed))|
+            {
+                let mut strip_rng = R::seed_from_u64(seed);
+                let first_row     = strip*strip_rows;
+                match bond_activation
+                {
+                    BondActivation::PerBond       => Self::label_strip(spins, parent, boundary_bonds, first_row, &mut strip_rng, &mut PerBondSampler::new(proba_add)),
+                    BondActivation::GeometricSkip =>
+                    {
+                        let mut sampler = GeometricSkipSampler::new(&mut strip_rng, proba_add);
+                        Self::label_strip(spins, parent, boundary_bonds, first_row, &mut strip_rng, &mut sampler)
+                    }
+                }
+            })
+            .reduce(|| (0_f64, 0_f64), |(e1, s1), (e2, s2)| (e1+e2, s1+s2));
+
+        // stitching: sequential, but only ~cols bonds per strip
+        for &(site1, site2) in self.boundary_bonds.iter().flatten()
+        {
+            union(&mut self.parent, 0, site1, site2);
+        }
+        (energy, spin_sum)
+    }
+
+    // Clusters of the last perform_swendsen_wang_all
+    pub fn cluster_map(&self) -> ClusterMap
+    {
+        let roots = (0..self.parent.len()).map(|site| find_readonly(&self.parent, site));
+        ClusterMap::from_cluster_indices(self.rows, self.cols, roots)
+    }
+
+    pub fn flip_cluster_and_take_fourier<S: SpinLattice>(&mut self, spins: &mut S) -> (f64, Complex64)
+    {
+        let parent    = &self.parent;
+        let flip_seed = self.flip_seed;
+        let cols      = self.cols;
+        spins.par_flip_where(|y, x| should_flip(flip_seed, find_readonly(parent, y as usize * cols + x as usize)));
+
+        if !self.take_fourier_transform
+        {
+            return (f64::default(), Complex64::default());
+        }
+        let factor  = 1_f64 / ((self.rows*self.cols) as f64).sqrt();
+        let kernels = &self.fourier_kernels;
+        let spins   = &*spins;
+        let row_sums: Vec<(f64, Complex64)> = (0..self.rows as i32).into_par_iter()
+            .map(|y|
+            {
+                spins.columns().fold((0_f64, Complex64::default()), |(q0, qx), x|
+                {
+                    let s = spins.at(y, x) as f64;
+                    (q0 + factor * s, qx + factor * s * kernels[x as usize])
+                })
+            })
+            .collect();
+        // summed in a fixed order: the result does not depend on the number of threads
+        row_sums.into_iter().fold((0_f64, Complex64::default()), |(q0, qx), (row_q0, row_qx)| (q0 + row_q0, qx + row_qx))
+    }
+}
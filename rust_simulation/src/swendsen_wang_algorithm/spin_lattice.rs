@@ -0,0 +1,75 @@
+use rand::Rng;
+use super::ising_array_2d::{IsingArray2D, MonteCarloModulo};
+
+// Common interface of the spin storage backends (IsingArray2D: one i8 per spin, BitPackedIsingArray2D: one bit per spin).
+// Spins are always read as +1/-1, positions are (row, column) = (y, x) with (0,0) on the top left.
+pub trait SpinLattice: Send + Sync
+{
+    fn new_polarized(rows: usize, cols: usize) -> Self where Self: Sized;
+    fn at(&self, i: i32, j: i32) -> i8;
+    fn flip_at(&mut self, i: i32, j: i32);
+    fn shape(&self) -> (i32, i32);
+    // Flips every spin (i, j) for which should_flip(i, j) is true, in parallel
+    fn par_flip_where<F: Fn(i32, i32) -> bool + Sync>(&mut self, should_flip: F) where Self: Sized;
+
+    // Conversions used for checkpoints & configuration files, which always store one i8 per spin
+    fn to_ising_array(&self) -> IsingArray2D;
+    fn from_ising_array(spins: IsingArray2D) -> Self where Self: Sized;
+
+    #[inline(always)]
+    fn at_periodic(&self, i: i32, j: i32) -> i8
+    {
+        let (rows, cols) = self.shape();
+        self.at(i.modulo(rows), j.modulo(cols))
+    }
+    #[inline(always)]
+    fn at_pos(&self, pos: (i32, i32)) -> i8 
+    {
+        self.at(pos.0, pos.1)
+    }
+    #[inline(always)]
+    fn at_pos_periodic(&self, pos: (i32, i32)) -> i8 
+    {
+        self.at_periodic(pos.0, pos.1)
+    }
+    #[inline(always)]
+    fn get_ngbrs_spin_sum(&self, pos: (i32, i32)) -> i8
+    {
+        self.at(pos.0 + 1, pos.1) + self.at(pos.0, pos.1+1)
+    }
+    #[inline(always)]
+    fn get_ngbrs_spin_sum_pbc(&self, pos: (i32, i32)) -> i8
+    {
+        self.at_periodic(pos.0 + 1, pos.1) + self.at_periodic(pos.0, pos.1+1)
+    }
+    #[inline(always)]
+    fn rows(&self) -> std::ops::Range<i32>
+    {
+        0..self.shape().0
+    }
+    #[inline(always)]
+    fn columns(&self) -> std::ops::Range<i32>
+    {
+        0..self.shape().1
+    }
+    // Same draws in the same order for every backend: a given seed gives the same configuration
+    fn randomize_spins<R: Rng + ?Sized>(&mut self, rng: &mut R) where Self: Sized
+    {
+        for i in self.rows()
+        {
+            for j in self.columns()
+            {
+                if rng.random_bool(0.5)
+                {
+                    self.flip_at(i, j);
+                }
+            }
+        }
+    }
+    fn new_randomized<R: Rng + ?Sized>(rng: &mut R, rows: usize, cols: usize) -> Self where Self: Sized
+    {
+        let mut spins = Self::new_polarized(rows, cols);
+        spins.randomize_spins(rng);
+        spins
+    }
+}
@@ -0,0 +1,88 @@
+use crate::statistics::RunningMoments;
+
+
+// How many sweeps are discarded before measuring:
+//      Fixed: therm_steps sweeps
+//      Auto:  a cold (fully polarised) replica is thermalised next to the usual random (hot) start, until the energies
+//             of both agree, see EquilibrationTest. therm_steps is then only an upper bound.
+//             The measurements continue from the hot replica, the sweeps actually used are reported per temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Thermalisation
+{
+    #[default]
+    Fixed,
+    Auto,
+}
+
+impl Thermalisation
+{
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Thermalisation::Fixed => "fixed",
+            Thermalisation::Auto  => "auto",
+        }
+    }
+}
+
+impl std::str::FromStr for Thermalisation
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "fixed" => Ok(Thermalisation::Fixed),
+            "auto"  => Ok(Thermalisation::Auto),
+            other => Err(format!("Unknown thermalisation \"{other}\" (expected fixed or auto)")),
+        }
+    }
+}
+
+
+// Energies of a hot & a cold start, sweep after sweep. Both relax towards the equilibrium energy from opposite sides:
+// they are taken as equilibrated once the means over the last half of the sweeps agree within TOLERANCE standard errors.
+// The errors ignore the autocorrelation, which makes them too small: the test errs on the side of thermalising longer.
+#[derive(Debug, Clone, Default)]
+pub struct EquilibrationTest
+{
+    hot: Vec<f64>,
+    cold: Vec<f64>,
+}
+
+impl EquilibrationTest
+{
+    pub const MIN_SWEEPS: usize     = 20;
+    pub const CHECK_INTERVAL: usize = 10;
+    pub const TOLERANCE: f64        = 2_f64;
+
+    pub fn push(&mut self, hot_energy: f64, cold_energy: f64)
+    {
+        self.hot.push(hot_energy);
+        self.cold.push(cold_energy);
+    }
+    #[inline]
+    pub fn sweeps(&self) -> usize
+    {
+        self.hot.len()
+    }
+    // checked every CHECK_INTERVAL sweeps only, the test costs O(sweeps)
+    pub fn is_equilibrated(&self) -> bool
+    {
+        let sweeps = self.sweeps();
+        if sweeps < Self::MIN_SWEEPS || !sweeps.is_multiple_of(Self::CHECK_INTERVAL)
+        {
+            return false;
+        }
+        let second_half = |energies: &[f64]| -> RunningMoments
+        {
+            let mut moments = RunningMoments::default();
+            energies[sweeps / 2..].iter().for_each(|&energy| moments.push(energy));
+            moments
+        };
+        let (hot, cold) = (second_half(&self.hot), second_half(&self.cold));
+        let error       = ((hot.variance() + cold.variance()) / hot.count() as f64).sqrt();
+        (hot.mean() - cold.mean()).abs() <= Self::TOLERANCE * error
+    }
+}
@@ -0,0 +1,113 @@
+use num::complex::Complex64;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+
+// Raw per-sweep measurements, one record per measurement sweep:
+//      energy, spin_sum, re_spin_q0, re_spin_qx, im_spin_qx
+// energy & spin_sum are totals over the lattice (spin_sum is signed) taken before the cluster flip,
+// the Fourier amplitudes are taken after it and normalised by 1/sqrt(N) as in
+// SwendsenWangAlgorithm::flip_cluster_and_take_fourier (zero if not measured).
+// Binary files contain the same 5 columns as little-endian f64, with no header:
+//      np.fromfile(file_name, dtype="<f8").reshape(-1, 5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSeriesFormat
+{
+    Csv,
+    Binary,
+}
+
+impl TimeSeriesFormat
+{
+    pub fn extension(&self) -> &'static str
+    {
+        match self
+        {
+            TimeSeriesFormat::Csv    => "csv",
+            TimeSeriesFormat::Binary => "bin",
+        }
+    }
+}
+
+impl FromStr for TimeSeriesFormat
+{
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        match s.trim().to_lowercase().as_str()
+        {
+            "csv"    => Ok(TimeSeriesFormat::Csv),
+            "binary" | "bin" => Ok(TimeSeriesFormat::Binary),
+            other    => Err(format!("Unknown time series format \"{other}\" (expected csv or binary)")),
+        }
+    }
+}
+
+pub const TIME_SERIES_COLUMNS: [&str; 5] = ["energy", "spin_sum", "re_spin_q0", "re_spin_qx", "im_spin_qx"];
+
+pub struct TimeSeriesWriter
+{
+    writer: BufWriter<File>,
+    format: TimeSeriesFormat,
+}
+
+impl TimeSeriesWriter
+{
+    pub fn create<P: AsRef<Path>>(file_name: P, format: TimeSeriesFormat) -> std::io::Result<Self>
+    {
+        let mut writer = BufWriter::new(File::create(file_name)?);
+        if format == TimeSeriesFormat::Csv
+        {
+            writeln!(&mut writer, "{}", TIME_SERIES_COLUMNS.join(", "))?;
+        }
+        Ok(Self { writer, format })
+    }
+    // Re-opens an existing time series, keeping only its first `records` records (restart from a checkpoint).
+    pub fn resume<P: AsRef<Path>>(file_name: P, format: TimeSeriesFormat, records: usize) -> std::io::Result<Self>
+    {
+        let file = std::fs::OpenOptions::new().read(true).write(true).open(&file_name)?;
+        let kept_length = match format
+        {
+            TimeSeriesFormat::Csv =>
+            {
+                let content = std::fs::read_to_string(&file_name)?;
+                content.split_inclusive('\n').take(records + 1).map(str::len).sum::<usize>() // +1: header
+            }
+            TimeSeriesFormat::Binary => records * TIME_SERIES_COLUMNS.len() * size_of::<f64>(),
+        };
+        file.set_len(kept_length as u64)?;
+
+        let mut writer = BufWriter::new(file);
+        writer.seek(SeekFrom::End(0))?;
+        Ok(Self { writer, format })
+    }
+    // "dir/out_rows=8,cols=8.txt" at T=2.25 => "dir/out_rows=8,cols=8_T=2.25.csv"
+    pub fn file_name_for(output_file: &str, temp: f64, format: TimeSeriesFormat) -> String
+    {
+        let path = Path::new(output_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("out");
+        let name = format!("{stem}_T={temp}.{}", format.extension());
+        path.with_file_name(name).to_string_lossy().into_owned()
+    }
+    pub fn write_sweep(&mut self, energy: f64, spin_sum: f64, spin_q0: f64, spin_qx: Complex64) -> std::io::Result<()>
+    {
+        let record = [energy, spin_sum, spin_q0, spin_qx.re, spin_qx.im];
+        match self.format
+        {
+            TimeSeriesFormat::Csv =>
+            {
+                writeln!(&mut self.writer, "{}, {}, {}, {}, {}", record[0], record[1], record[2], record[3], record[4])
+            }
+            TimeSeriesFormat::Binary =>
+            {
+                record.iter().try_for_each(|value| self.writer.write_all(&value.to_le_bytes()))
+            }
+        }
+    }
+    pub fn flush(&mut self) -> std::io::Result<()>
+    {
+        self.writer.flush()
+    }
+}
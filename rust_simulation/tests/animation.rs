@@ -0,0 +1,57 @@
+// Animated GIF of the Swendsen-Wang updates
+use swendsen_wang::animation::Animation;
+use swendsen_wang::configuration::InitialState;
+
+#[test]
+fn frames_alternate_clusters_and_spins()
+{
+    let animation = Animation { rows: 6, cols: 8, temperature: 2.0, therm_steps: 3, steps: 4, show_clusters: true, scale: 1, seed: 5, ..Default::default() };
+    let frames    = animation.record().unwrap();
+    assert_eq!(frames.len(), 1 + 2 * 4);
+    assert_eq!(frames, animation.record().unwrap());
+    assert_eq!(Animation { show_clusters: false, ..animation.clone() }.record().unwrap().len(), 1 + 4);
+
+    let is_spin_frame = |pixels: &[u8]| pixels.iter().all(|&index| [Animation::UP, Animation::DOWN].contains(&index));
+    for (step, triple) in frames.windows(3).step_by(2).enumerate()
+    {
+        let [before, clusters, after] = triple else { unreachable!() };
+        assert!(is_spin_frame(before.pixels()) && is_spin_frame(after.pixels()), "step {step}");
+        assert!(clusters.pixels().iter().all(|&index| index >= 2), "step {step}");
+        // every cluster (48 sites: one colour each) holds parallel spins, which are all flipped or all kept
+        for (site, &index) in clusters.pixels().iter().enumerate()
+        {
+            let root = clusters.pixels().iter().position(|&other| other == index).unwrap();
+            let same = |frame: &swendsen_wang::snapshot::Image| frame.pixels()[site] == frame.pixels()[root];
+            assert!(same(before) && same(after), "step {step}, site {site}");
+        }
+    }
+}
+
+#[test]
+fn animations_are_looping_gifs()
+{
+    let file_name = std::env::temp_dir().join("animation.gif").to_string_lossy().into_owned();
+    let animation = Animation { rows: 10, cols: 12, temperature: 2.269, steps: 3, show_clusters: true, scale: 2, frame_delay: 20, initial_state: InitialState::Up, ..Default::default() };
+    assert_eq!(animation.write_to_file(&file_name).unwrap(), 7);
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(std::fs::File::open(&file_name).unwrap()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (24, 20));
+    assert_eq!(decoder.global_palette().unwrap(), Animation::palette().as_slice());
+    let recorded = animation.record().unwrap();
+    let mut num_frames = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap()
+    {
+        assert_eq!(frame.delay, 20);
+        assert_eq!(frame.buffer.as_ref(), recorded[num_frames].pixels());
+        num_frames += 1;
+    }
+    assert_eq!(num_frames, 7);
+    assert!(recorded[0].pixels().iter().all(|&index| index == Animation::UP));
+    std::fs::remove_file(&file_name).unwrap();
+
+    assert!(Animation { rows: 1, ..Default::default() }.record().is_err());
+    assert!(Animation { temperature: -1.0, ..Default::default() }.record().is_err());
+    assert!(Animation { cols: 20_000, scale: 4, ..Default::default() }.record().is_err());
+}
@@ -0,0 +1,45 @@
+// The geometric skip sampler must give independent Bernoulli(proba_add) bonds, like one random_bool per bond
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use swendsen_wang::swendsen_wang_algorithm::{BondSampler, GeometricSkipSampler, PerBondSampler};
+
+const NUM_BONDS: usize = 1_000_000;
+
+// (fraction of activated bonds, lag-1 autocovariance)
+fn bond_statistics<B: BondSampler>(sampler: &mut B, rng: &mut SmallRng) -> (f64, f64)
+{
+    let bonds: Vec<f64> = (0..NUM_BONDS).map(|_| sampler.activate(rng) as u8 as f64).collect();
+    let mean            = bonds.iter().sum::<f64>() / NUM_BONDS as f64;
+    let covariance      = bonds.windows(2).map(|pair| (pair[0] - mean)*(pair[1] - mean)).sum::<f64>() / (NUM_BONDS - 1) as f64;
+    (mean, covariance)
+}
+
+#[test]
+fn geometric_skip_is_bernoulli()
+{
+    let mut rng = SmallRng::seed_from_u64(2024);
+    for proba_add in [0.01, 0.2, 0.5, 0.63, 0.95, 0.9999]
+    {
+        let variance = proba_add*(1_f64 - proba_add);
+        let sigma    = (variance / NUM_BONDS as f64).sqrt();
+
+        let mut geometric      = GeometricSkipSampler::new(&mut rng, proba_add);
+        let (mean, covariance) = bond_statistics(&mut geometric, &mut rng);
+        let (per_bond_mean, _) = bond_statistics(&mut PerBondSampler::new(proba_add), &mut rng);
+
+        assert!((mean - proba_add).abs() < 5_f64*sigma, "p={proba_add}: mean {mean}");
+        assert!((per_bond_mean - proba_add).abs() < 5_f64*sigma, "p={proba_add}: per bond mean {per_bond_mean}");
+        assert!(covariance.abs() < 5_f64*variance / (NUM_BONDS as f64).sqrt(), "p={proba_add}: lag-1 covariance {covariance}");
+    }
+}
+
+#[test]
+fn geometric_skip_limits()
+{
+    let mut rng    = SmallRng::seed_from_u64(1);
+    let mut never  = GeometricSkipSampler::new(&mut rng, 0_f64);
+    let mut always = GeometricSkipSampler::new(&mut rng, 1_f64);
+    assert!((0..10_000).all(|_| !never.activate(&mut rng)));
+    assert!((0..10_000).all(|_| always.activate(&mut rng)));
+}
@@ -0,0 +1,48 @@
+// Checkpointed runs stopped partway & resumed
+use swendsen_wang::checkpoint::Checkpoint;
+use swendsen_wang::random::RngKind;
+use swendsen_wang::simulation::Simulation;
+
+#[test]
+fn resumed_runs_match_uninterrupted_ones()
+{
+    let output_file  = std::env::temp_dir().join("checkpoint_rows=8,cols=12.txt").to_string_lossy().into_owned();
+    let temperatures = [1.8, 2.269, 3.0];
+    let simulation   = |rng: RngKind, measure_steps: usize, seed: u64, checkpoint_interval: usize, resume: bool| Simulation::builder()
+        .lattice(8, 12)
+        .temperatures(&temperatures)
+        .sweeps(40, measure_steps)
+        .measure_structure_factor(true)
+        .rng(rng)
+        .seed(seed)
+        .output_file(&output_file)
+        .checkpoint_interval(checkpoint_interval)
+        .resume(resume)
+        .build()
+        .unwrap();
+
+    for rng in [RngKind::Small, RngKind::ChaCha, RngKind::MersenneTwister, RngKind::Pcg]
+    {
+        // checkpoints leave the random streams untouched
+        let reference = simulation(rng, 150, 31, 0, false).run();
+        assert_eq!(simulation(rng, 150, 31, 20, false).run(), reference, "{rng:?}");
+        for &temp in &temperatures
+        {
+            assert_eq!(Checkpoint::read_from_file(&Checkpoint::file_name_for(&output_file, temp)).unwrap().sweeps_done, 40 + 150);
+        }
+
+        // stopped after 60 of the 150 measurements, off the checkpoint interval of the other runs
+        simulation(rng, 60, 31, 7, false).run();
+        for &temp in &temperatures
+        {
+            let checkpoint = Checkpoint::read_from_file(&Checkpoint::file_name_for(&output_file, temp)).unwrap();
+            assert_eq!((checkpoint.temperature, checkpoint.sweeps_done, checkpoint.therm_steps), (temp, 40 + 60, 40));
+        }
+        // the random streams are restored from the checkpoints: another seed only shows if they are not used
+        let resumed = simulation(rng, 150, 32, 20, true);
+        let results = resumed.run();
+        resumed.remove_checkpoints();
+        assert_eq!(results, reference, "{rng:?}");
+        assert_ne!(simulation(rng, 150, 32, 0, false).run(), reference, "{rng:?}");
+    }
+}
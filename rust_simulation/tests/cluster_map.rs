@@ -0,0 +1,129 @@
+// Bond percolation step on its own: cluster map consistency & caller-chosen flips
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use swendsen_wang::swendsen_wang_algorithm::{IsingArray2D, Label, LabellingStrategy, SwendsenWangAlgorithm, UnionFindStrategy, J};
+
+fn proba_add(temp: f64) -> f64
+{
+    1f64 - (-2_f64*J/temp).exp()
+}
+
+#[test]
+fn clusters_are_single_spin_and_sizes_add_up()
+{
+    let (rows, cols) = (24, 31);
+    let mut rng      = SmallRng::seed_from_u64(3);
+    let spins        = IsingArray2D::new_randomized(&mut rng, rows, cols);
+
+    for labelling in [LabellingStrategy::HoshenKopelman, LabellingStrategy::FloodFill]
+    {
+        let mut swendsen_wang = SwendsenWangAlgorithm::<usize>::with_labelling(rows, cols, labelling, UnionFindStrategy::default());
+        for temp in [1.0, 2.269, 5.0]
+        {
+            let map = swendsen_wang.bond_percolation(&spins, &mut rng, proba_add(temp));
+
+            assert_eq!(map.shape(), (rows, cols));
+            assert_eq!(map.sizes().iter().sum::<usize>(), rows*cols);
+            assert_eq!(map.labels().iter().max().map(|&label| label + 1), Some(map.num_clusters()));
+            for label in 0..map.num_clusters()
+            {
+                let sites: Vec<(i32, i32)> = map.sites_of(label).collect();
+                assert_eq!(sites.len(), map.size_of(label));
+                assert!(sites.iter().all(|&(i, j)| spins.at(i, j) == spins.at(sites[0].0, sites[0].1)), "a cluster mixes up & down spins");
+            }
+            let largest = map.largest_cluster().unwrap();
+            assert_eq!(map.size_of(largest), *map.sizes().iter().max().unwrap());
+        }
+    }
+}
+
+#[test]
+fn percolation_is_reproducible_across_labellings()
+{
+    let (rows, cols)  = (16, 16);
+    let mut rng       = SmallRng::seed_from_u64(5);
+    let spins         = IsingArray2D::new_randomized(&mut rng, rows, cols);
+    let mut percolate = SwendsenWangAlgorithm::<usize>::new(rows, cols);
+    let mut flood     = SwendsenWangAlgorithm::<usize>::with_labelling(rows, cols, LabellingStrategy::FloodFill, UnionFindStrategy::default());
+
+    // same seed: same bonds for both labellings
+    let map_hk    = percolate.bond_percolation(&spins, &mut SmallRng::seed_from_u64(9), proba_add(2.269));
+    let map_flood = flood.bond_percolation(&spins, &mut SmallRng::seed_from_u64(9), proba_add(2.269));
+    assert_eq!(map_hk, map_flood);
+
+    // the percolation step left the algorithm ready for the next one
+    let map_again = percolate.bond_percolation(&spins, &mut SmallRng::seed_from_u64(9), proba_add(2.269));
+    assert_eq!(map_hk, map_again);
+}
+
+#[test]
+fn caller_chosen_flips()
+{
+    let (rows, cols)      = (12, 20);
+    let mut rng           = SmallRng::seed_from_u64(8);
+    let mut spins         = IsingArray2D::new_randomized(&mut rng, rows, cols);
+    let original          = IsingArray2D::from_spins(rows, cols, spins.as_slice().to_vec()).unwrap();
+    let mut swendsen_wang = SwendsenWangAlgorithm::<usize>::new(rows, cols);
+    let map               = swendsen_wang.bond_percolation(&spins, &mut rng, proba_add(2.0));
+
+    assert_eq!(map.flip_clusters(&mut spins, |_, _| false), 0);
+    assert_eq!(spins.as_slice(), original.as_slice());
+
+    assert_eq!(map.flip_clusters(&mut spins, |_, _| true), rows*cols);
+    assert!(spins.as_slice().iter().zip(original.as_slice()).all(|(&s, &s0)| s == -s0));
+    map.flip_clusters(&mut spins, |_, _| true);
+
+    // Wolff-like: only the cluster of one site
+    let label   = map.label_at(3, 7);
+    let flipped = map.flip_clusters(&mut spins, |l, _| l == label);
+    assert_eq!(flipped, map.size_of(label));
+    for i in 0..rows as i32
+    {
+        for j in 0..cols as i32
+        {
+            let expected = if map.label_at(i, j) == label { -original.at(i, j) } else { original.at(i, j) };
+            assert_eq!(spins.at(i, j), expected);
+        }
+    }
+
+    // Swendsen-Wang rule: the rule is called once per cluster
+    let mut calls = 0;
+    map.flip_clusters(&mut spins, |_, _| { calls += 1; rng.random_bool(0.5) });
+    assert_eq!(calls, map.num_clusters());
+}
+
+// labels follow the first site of each cluster: one draw per cluster, in that order
+fn assert_one_draw_per_cluster<L: Label>(labelling: LabellingStrategy, lattice_seed: u64)
+{
+    let (rows, cols)      = (20, 28);
+    let mut rng           = SmallRng::seed_from_u64(12);
+    let mut swendsen_wang = SwendsenWangAlgorithm::<L>::with_labelling(rows, cols, labelling, UnionFindStrategy::default());
+
+    for temp in [1.0, 2.269, 5.0]
+    {
+        let mut spins = IsingArray2D::new_randomized(&mut SmallRng::seed_from_u64(lattice_seed), rows, cols);
+        swendsen_wang.perform_swendsen_wang_all(&spins, &mut rng, proba_add(temp));
+        let map = swendsen_wang.cluster_map(&spins);
+
+        let mut expected     = spins.clone();
+        let mut predictor    = rng.clone();
+        let flips: Vec<bool> = (0..map.num_clusters()).map(|_| predictor.random::<f32>() < 0.5).collect();
+        map.flip_clusters(&mut expected, |label, _| flips[label]);
+
+        swendsen_wang.flip_cluster_and_take_fourier(&mut spins, &mut rng);
+        swendsen_wang.reset();
+        assert_eq!(spins.as_slice(), expected.as_slice(), "{labelling:?} at T={temp}");
+        assert_eq!(rng.random::<u64>(), predictor.random::<u64>(), "{labelling:?} at T={temp}");
+    }
+}
+
+#[test]
+fn clusters_are_flipped_with_one_draw_each()
+{
+    for labelling in [LabellingStrategy::HoshenKopelman, LabellingStrategy::FloodFill]
+    {
+        assert_one_draw_per_cluster::<usize>(labelling, 1);
+        assert_one_draw_per_cluster::<u32>(labelling, 2);
+    }
+}
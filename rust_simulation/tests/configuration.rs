@@ -0,0 +1,128 @@
+// Initial states, configuration files & annealing
+use std::time::Duration;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use swendsen_wang::configuration::{configuration_file_name_for, read_configuration, write_configuration, InitialState};
+use swendsen_wang::monte_carlo_results::{MonteCarloResults, OutputFormat};
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::swendsen_wang_algorithm::{BitPackedIsingArray2D, IsingArray2D, SpinLattice};
+
+#[test]
+fn configurations_round_trip()
+{
+    let file_name = std::env::temp_dir().join("round_trip.spins").to_string_lossy().into_owned();
+    let spins     = IsingArray2D::from_spins(3, 4, vec![1, -1, 1, 1, -1, -1, 1, -1, 1, 1, 1, -1]).unwrap();
+    write_configuration(&file_name, &spins, &[("temperature", "2.5".to_string())]).unwrap();
+    let content = std::fs::read_to_string(&file_name).unwrap();
+    assert_eq!(content, "# temperature: 2.5\n+-++\n--+-\n+++-\n");
+    assert!(read_configuration(&file_name).unwrap() == spins);
+
+    for malformed in ["++\n+\n", "+x\n", "# nothing\n"]
+    {
+        std::fs::write(&file_name, malformed).unwrap();
+        assert_eq!(read_configuration(&file_name).unwrap_err().kind(), std::io::ErrorKind::InvalidData, "{malformed:?}");
+    }
+    std::fs::write(&file_name, "# written by hand\n1100\n0011\n").unwrap();
+    assert_eq!(read_configuration(&file_name).unwrap().as_slice(), [1, 1, -1, -1, -1, -1, 1, 1]);
+    std::fs::remove_file(&file_name).unwrap();
+}
+
+#[test]
+fn initial_states()
+{
+    assert_eq!("random".parse(), Ok(InitialState::Random));
+    assert_eq!(" Cold".parse(), Ok(InitialState::Up));
+    assert_eq!("domain_wall".parse(), Ok(InitialState::Striped));
+    assert_eq!("dir/Start.spins".parse(), Ok(InitialState::File("dir/Start.spins".to_string())));
+
+    assert!(InitialState::Random.configuration(4, 6).unwrap().is_none());
+    let down = InitialState::Down.configuration(4, 6).unwrap().unwrap();
+    assert!(down.as_slice().iter().all(|&spin| spin == -1));
+    let striped = InitialState::Striped.configuration(4, 6).unwrap().unwrap();
+    assert_eq!(striped.as_slice().iter().map(|&spin| spin as i32).sum::<i32>(), 0);
+    assert_eq!(striped.at(1, 5), 1);
+    assert_eq!(striped.at(2, 0), -1);
+
+    let missing = InitialState::File("no_such_file.spins".to_string());
+    assert!(Simulation::builder().lattice(4, 6).temperatures(&[2.0]).sweeps(1, 1).initial_state(missing).build().is_err());
+}
+
+// one draw per site in row major order, on every site of non-square lattices (which used to be swapped)
+#[test]
+fn random_states_cover_non_square_lattices()
+{
+    for (rows, cols) in [(4, 16), (16, 4), (5, 7)]
+    {
+        let mut rng           = SmallRng::seed_from_u64(9);
+        let expected: Vec<i8> = (0..rows * cols).map(|_| if rng.random_bool(0.5) { -1 } else { 1 }).collect();
+        let standard          = IsingArray2D::new_randomized(&mut SmallRng::seed_from_u64(9), rows, cols);
+        let compact           = BitPackedIsingArray2D::new_randomized(&mut SmallRng::seed_from_u64(9), rows, cols);
+        assert_eq!(standard.as_slice(), expected.as_slice(), "{rows}x{cols}");
+        assert_eq!(compact.to_ising_array().as_slice(), expected.as_slice(), "{rows}x{cols}");
+    }
+}
+
+#[test]
+fn saved_configurations_restart_runs()
+{
+    let output_file  = std::env::temp_dir().join("configuration_rows=12,cols=16.txt").to_string_lossy().into_owned();
+    let temperatures = [0.5, 3.0];
+    let simulation   = |initial_state: InitialState, save_configurations: bool| Simulation::builder()
+        .lattice(12, 16)
+        .temperatures(&temperatures)
+        .sweeps(0, 5)
+        .seed(1)
+        .initial_state(initial_state)
+        .output_file(&output_file)
+        .save_configurations(save_configurations)
+        .build()
+        .unwrap();
+    assert!(Simulation::builder().lattice(12, 16).temperatures(&temperatures).sweeps(0, 5).save_configurations(true).build().is_err());
+
+    // at low temperature the two domain walls of the striped state survive the few sweeps: E/N ~ -2 + 4 cols / N
+    let energy  = |results: &[swendsen_wang::monte_carlo_results::MonteCarloResults<f64>]| results[0].get("energy_density").unwrap();
+    let striped = simulation(InitialState::Striped, true).run();
+    let cold    = simulation(InitialState::Up, false).run();
+    assert!(energy(&striped) > -1.9 && energy(&cold) < -1.95, "{} {}", energy(&striped), energy(&cold));
+
+    // a configuration file of the striped state gives the same run, at a wrong size it fails
+    let saved = configuration_file_name_for(&output_file, temperatures[0]);
+    assert_eq!(read_configuration(&saved).unwrap().shape(), (12, 16));
+    write_configuration(&saved, &InitialState::Striped.configuration(12, 16).unwrap().unwrap(), &[]).unwrap();
+    let restarted = simulation(InitialState::File(saved.clone()), false);
+    assert_eq!(restarted.run(), striped);
+
+    // the saved file name holds a comma, which the header of the results must not
+    let results_file = std::env::temp_dir().join("configuration_results.txt").to_string_lossy().into_owned();
+    MonteCarloResults::write_as(OutputFormat::Legacy, &results_file, &temperatures, &striped, Duration::ZERO, &restarted.run_parameters()).unwrap();
+    let read = MonteCarloResults::<f64>::read_from_file(&results_file).unwrap();
+    std::fs::remove_file(&results_file).unwrap();
+    assert_eq!(read.metadata("initial_state"), Some("file configuration_rows=12;cols=16_T=0.5.spins"));
+    assert_eq!(read.results, striped);
+    assert!(Simulation::builder().lattice(16, 12).temperatures(&[1.0]).sweeps(0, 5).initial_state(InitialState::File(saved.clone())).build().is_err());
+
+    temperatures.iter().for_each(|&temp| std::fs::remove_file(configuration_file_name_for(&output_file, temp)).unwrap());
+}
+
+#[test]
+fn annealing_carries_the_configuration_over()
+{
+    // quenched from the disordered high temperature configuration, domains remain after a few sweeps
+    let temperatures = [6.0, 0.5];
+    let simulation   = |anneal: bool| Simulation::builder()
+        .lattice(32, 32)
+        .temperatures(&temperatures)
+        .sweeps(0, 3)
+        .seed(12)
+        .initial_state(InitialState::Up)
+        .anneal(anneal)
+        .build()
+        .unwrap()
+        .run();
+
+    let independent = simulation(false);
+    let annealed    = simulation(true);
+    assert_eq!(independent[0], annealed[0]);
+    let (ordered, quenched) = (independent[1].get("energy_density").unwrap(), annealed[1].get("energy_density").unwrap());
+    assert!(quenched > ordered + 0.05, "{quenched} vs {ordered}");
+    assert_eq!(simulation(true), annealed);
+}
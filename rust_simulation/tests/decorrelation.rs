@@ -0,0 +1,148 @@
+// Sweeps between measurements & the throttled Fourier transform
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use swendsen_wang::checkpoint::Checkpoint;
+use swendsen_wang::observables::{Measurement, Observable, Summary};
+use swendsen_wang::simulation::{MeasurementInterval, Simulation};
+use swendsen_wang::statistics::integrated_autocorrelation_time;
+
+#[test]
+fn autocorrelation_time_of_ar1_series()
+{
+    let mut rng = SmallRng::seed_from_u64(4);
+    for rho in [0_f64, 0.5, 0.9]
+    {
+        let mut x = 0_f64;
+        let series: Vec<f64> = (0..200_000).map(|_| { x = rho * x + rng.random_range(-1_f64..1_f64); x }).collect();
+        let expected = (1_f64 + rho) / (2_f64 * (1_f64 - rho));
+        let tau      = integrated_autocorrelation_time(&series);
+        assert!((tau / expected - 1_f64).abs() < 0.1, "rho={rho}: {tau} != {expected}");
+    }
+    assert_eq!(integrated_autocorrelation_time(&[3_f64; 100]), 0.5);
+    assert_eq!(integrated_autocorrelation_time(&[]), 0.5);
+}
+
+// number of measurements & of those with a Fourier transform
+#[derive(Default)]
+struct MeasurementCounter
+{
+    measured: usize,
+    transformed: usize,
+}
+
+impl Observable for MeasurementCounter
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        self.measured    += 1;
+        self.transformed += measurement.has_fourier_transform as usize;
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        assert_eq!(self.measured, summary.num_measurements);
+        vec![("measured".to_string(), self.measured as f64), ("transformed".to_string(), self.transformed as f64)]
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        vec![self.measured as f64, self.transformed as f64]
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[measured, transformed, ..] = state else
+        {
+            return None;
+        };
+        *self = Self { measured: measured as usize, transformed: transformed as usize };
+        Some(2)
+    }
+}
+
+#[test]
+fn measurements_are_spaced_and_transforms_throttled()
+{
+    let temperatures = [2.0, 2.5];
+    let simulation   = |measurement_interval: MeasurementInterval, fourier_interval: usize| Simulation::builder()
+        .lattice(12, 12)
+        .temperatures(&temperatures)
+        .sweeps(50, 200)
+        .sweeps_per_measurement(measurement_interval)
+        .measure_structure_factor(true)
+        .fourier_interval(fourier_interval)
+        .observable(MeasurementCounter::default)
+        .seed(17)
+        .build()
+        .unwrap()
+        .run();
+
+    let every_sweep = simulation(MeasurementInterval::Every(1), 1);
+    let spaced      = simulation(MeasurementInterval::Every(3), 4);
+    for (reference, res) in every_sweep.iter().zip(&spaced)
+    {
+        assert_eq!((reference.get("measured"), reference.get("transformed")), (Some(200_f64), Some(200_f64)));
+        assert_eq!((res.get("measured"), res.get("transformed")), (Some(200_f64), Some(50_f64)));
+        assert!(res.get("correlation length").unwrap() > 0_f64);
+    }
+
+    // the Fourier transform uses no random numbers: throttling it only changes the correlation length
+    let throttled = simulation(MeasurementInterval::Every(1), 4);
+    for (reference, res) in every_sweep.iter().zip(&throttled)
+    {
+        assert!(res.iter().filter(|(name, _)| !["correlation length", "transformed"].contains(name)).all(|(name, value)| reference.get(name) == Some(value)));
+    }
+    assert!(every_sweep[0].get("sweeps_per_measurement").is_none());
+}
+
+#[test]
+fn automatic_interval_follows_the_autocorrelation()
+{
+    let temperatures = [2.269, 5.0];
+    let results = Simulation::builder()
+        .lattice(24, 24)
+        .temperatures(&temperatures)
+        .sweeps(100, 100)
+        .sweeps_per_measurement(MeasurementInterval::Auto)
+        .seed(2)
+        .build()
+        .unwrap()
+        .run();
+
+    let interval = |res: &swendsen_wang::monte_carlo_results::MonteCarloResults<f64>| res.get("sweeps_per_measurement").unwrap();
+    assert!(results.iter().all(|res| interval(res) >= 1_f64));
+    assert!(interval(&results[0]) > interval(&results[1]), "{} <= {}", interval(&results[0]), interval(&results[1]));
+
+    assert_eq!("auto".parse::<MeasurementInterval>(), Ok(MeasurementInterval::Auto));
+    assert_eq!(" 4 ".parse::<MeasurementInterval>(), Ok(MeasurementInterval::Every(4)));
+    assert!("0".parse::<MeasurementInterval>().is_err());
+    let valid = || Simulation::builder().lattice(8, 8).temperatures(&[2.0]).sweeps(10, 10);
+    assert!(valid().sweeps_per_measurement(MeasurementInterval::Every(0)).build().is_err());
+}
+
+#[test]
+fn resumed_runs_keep_their_interval()
+{
+    let output_file = std::env::temp_dir().join("decorrelation_rows=10,cols=10.txt").to_string_lossy().into_owned();
+    let temperature = 2.3;
+    let simulation  = |measure_steps: usize, resume: bool| Simulation::builder()
+        .lattice(10, 10)
+        .temperatures(&[temperature])
+        .sweeps(100, measure_steps)
+        .sweeps_per_measurement(MeasurementInterval::Auto)
+        .seed(6)
+        .output_file(&output_file)
+        .checkpoint_interval(1)
+        .resume(resume)
+        .build()
+        .unwrap();
+
+    let reference  = simulation(150, false).run();
+    let checkpoint = Checkpoint::read_from_file(&Checkpoint::file_name_for(&output_file, temperature)).unwrap();
+    let interval   = reference[0].get("sweeps_per_measurement").unwrap() as usize;
+    assert_eq!(checkpoint.sweeps_per_measurement, interval);
+    assert_eq!(checkpoint.therm_steps, 100 + MeasurementInterval::PILOT_SWEEPS);
+    assert_eq!(checkpoint.sweeps_done, 100 + MeasurementInterval::PILOT_SWEEPS + 150 * interval);
+
+    simulation(50, false).run();
+    let resumed = simulation(150, true);
+    let results = resumed.run();
+    resumed.remove_checkpoints();
+    assert_eq!(results, reference);
+}
@@ -0,0 +1,122 @@
+// Exact enumeration of small lattices, and the Swendsen-Wang simulation checked against it
+use swendsen_wang::exact::ExactEnumeration;
+use swendsen_wang::monte_carlo_results::MonteCarloResults;
+use swendsen_wang::simulation::{Sampler, Simulation};
+use swendsen_wang::statistics::RunningMoments;
+use swendsen_wang::swendsen_wang_algorithm::{LabellingStrategy, LatticeBackend};
+
+#[test]
+fn two_by_two_lattice()
+{
+    // 8 bonds (two between each pair of neighbours): 2 aligned states at E=-8, 12 states at E=0
+    // (one flipped spin, or two rows / columns), 2 checkerboards at E=8
+    let exact = ExactEnumeration::new(2, 2).unwrap();
+    assert_eq!(exact.density_of_states().collect::<Vec<_>>(), [(-8_f64, 2_f64), (0_f64, 12_f64), (8_f64, 2_f64)]);
+
+    let temp       = 2_f64;
+    let boltzmann  = |energy: f64| (-energy / temp).exp();
+    let z          = 2_f64 * boltzmann(-8_f64) + 12_f64 + 2_f64 * boltzmann(8_f64);
+    let energy     = (-16_f64 * boltzmann(-8_f64) + 16_f64 * boltzmann(8_f64)) / z;
+    let energy_sqr = (128_f64 * boltzmann(-8_f64) + 128_f64 * boltzmann(8_f64)) / z;
+    // |M| = 4 for the aligned states, 2 for the single flips (8 of the 12)
+    let abs_m      = (2_f64 * 4_f64 * boltzmann(-8_f64) + 8_f64 * 2_f64) / (4_f64 * z);
+
+    let results = exact.at(temp);
+    assert!((results.log_partition_function - z.ln()).abs() < 1e-12);
+    assert!((results.energy_density - energy / 4_f64).abs() < 1e-12);
+    assert!((results.specific_heat - (energy_sqr - energy * energy) / (4_f64 * temp * temp)).abs() < 1e-12);
+    assert!((results.magnetisation - abs_m).abs() < 1e-12);
+    assert!(ExactEnumeration::new(5, 6).is_err() && ExactEnumeration::new(1, 8).is_err());
+}
+
+#[test]
+fn thermodynamic_identities()
+{
+    let exact = ExactEnumeration::new(3, 5).unwrap();
+    assert_eq!(exact.density_of_states().map(|(_, states)| states).sum::<f64>(), 2_f64.powi(15));
+
+    let (temp, dt) = (2.2, 1e-4);
+    let (below, at, above) = (exact.at(temp - dt), exact.at(temp), exact.at(temp + dt));
+    // d ln Z / d(1/T) = -<E> & d<E>/dT = N C
+    let d_log_z = (above.log_partition_function - below.log_partition_function) / (1_f64 / (temp + dt) - 1_f64 / (temp - dt));
+    assert!((d_log_z / 15_f64 + at.energy_density).abs() < 1e-6, "{d_log_z}");
+    let d_energy = (above.energy_density - below.energy_density) / (2_f64 * dt);
+    assert!((d_energy - at.specific_heat).abs() < 1e-6, "{d_energy} != {}", at.specific_heat);
+
+    // ordered & disordered limits
+    let (cold, hot) = (exact.at(0.05), exact.at(1e6));
+    assert!((cold.energy_density + 2_f64).abs() < 1e-12 && (cold.magnetisation - 1_f64).abs() < 1e-12);
+    assert!(hot.energy_density.abs() < 1e-4 && (hot.magnetisation_sqr - 1_f64 / 15_f64).abs() < 1e-4);
+    assert!((hot.structure_factor_qx - 1_f64).abs() < 1e-4 && (hot.structure_factor_q0 - 1_f64).abs() < 1e-4);
+}
+
+const COMPARED: [&str; 6] = ["energy_density", "magnetisation", "specific_heat", "susceptibility", "susceptibility_m2", "correlation length"];
+
+// Mean & standard error of every compared result over independent replicas, each one a temperature of its own stream
+fn replica_estimates(simulation: Simulation) -> Vec<(&'static str, f64, f64)>
+{
+    let results = simulation.run();
+    COMPARED.iter().map(|&name|
+    {
+        let mut moments = RunningMoments::default();
+        results.iter().for_each(|res| moments.push(res.get(name).unwrap()));
+        (name, moments.mean(), (moments.variance() / (moments.count() - 1) as f64).sqrt())
+    }).collect()
+}
+
+fn assert_agrees(estimates: &[(&str, f64, f64)], exact: &MonteCarloResults<f64>, context: &str)
+{
+    for &(name, mean, error) in estimates
+    {
+        let expected = exact.get(name).unwrap();
+        assert!((mean - expected).abs() <= 5_f64 * error + 1e-12, "{context} {name}: {mean} +- {error} != {expected}");
+    }
+}
+
+#[test]
+fn swendsen_wang_agrees_with_the_enumeration()
+{
+    const REPLICAS: usize = 16;
+    for (rows, cols) in [(2, 2), (3, 3), (4, 4), (3, 5), (5, 5)]
+    {
+        let exact = ExactEnumeration::new(rows, cols).unwrap();
+        for temp in [1.5, 2.269, 3.5]
+        {
+            let simulation = Simulation::builder()
+                .lattice(rows, cols)
+                .temperatures(&[temp; REPLICAS])
+                .sweeps(200, 4000)
+                .measure_structure_factor(true)
+                .seed(2024)
+                .build()
+                .unwrap();
+            assert_agrees(&replica_estimates(simulation), &exact.at(temp).results(), &format!("{rows}x{cols} at T={temp}"));
+        }
+    }
+}
+
+#[test]
+fn samplers_agree_with_the_enumeration()
+{
+    const REPLICAS: usize = 16;
+    let (rows, cols, temp) = (4, 4, 2.269);
+    let exact    = ExactEnumeration::new(rows, cols).unwrap().at(temp).results();
+    let samplers = [
+        Sampler { lattice_backend: LatticeBackend::Compact, ..Sampler::default() },
+        Sampler { strips: 2, ..Sampler::default() },
+        Sampler { labelling: LabellingStrategy::FloodFill, ..Sampler::default() },
+    ];
+    for sampler in samplers
+    {
+        let simulation = Simulation::builder()
+            .lattice(rows, cols)
+            .temperatures(&[temp; REPLICAS])
+            .sweeps(200, 4000)
+            .measure_structure_factor(true)
+            .sampler(sampler)
+            .seed(7)
+            .build()
+            .unwrap();
+        assert_agrees(&replica_estimates(simulation), &exact, &format!("{sampler:?}"));
+    }
+}
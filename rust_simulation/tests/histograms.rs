@@ -0,0 +1,86 @@
+// Magnetisation & energy distributions
+use swendsen_wang::observables::Histograms;
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::statistics::Histogram;
+
+#[test]
+fn values_fall_in_their_bins()
+{
+    let mut histogram = Histogram::new(-1_f64, 1_f64, 4);
+    for value in [-1_f64, -0.75, -0.5, 0_f64, 0.25, 0.5, 1_f64, 1.5, -2_f64]
+    {
+        histogram.push(value);
+    }
+    // both edges belong to the range, the upper one to the last bin
+    assert_eq!(histogram.counts(), [2, 1, 2, 2]);
+    assert_eq!(histogram.outside(), 2);
+    assert_eq!(histogram.total(), 9);
+    assert_eq!(histogram.bin_edges().collect::<Vec<_>>(), [(-1_f64, -0.5), (-0.5, 0_f64), (0_f64, 0.5), (0.5, 1_f64)]);
+    let integral: f64 = histogram.densities().map(|density| density * histogram.bin_width()).sum();
+    assert!((integral - 7_f64 / 9_f64).abs() < 1E-12);
+
+    let mut restored = Histogram::new(-1_f64, 1_f64, 4);
+    assert_eq!(restored.load(&histogram.to_vec()), Some(5));
+    assert_eq!(restored, histogram);
+    assert_eq!(restored.load(&[1_f64; 4]), None);
+}
+
+// (low, high, count, density) rows of a histogram file
+fn read_histogram(file_name: &str) -> Vec<(f64, f64, u64, f64)>
+{
+    let content = std::fs::read_to_string(file_name).unwrap();
+    let mut lines = content.lines().filter(|line| !line.starts_with('#'));
+    assert_eq!(lines.next(), Some("low,high,count,density"));
+    lines.map(|line|
+    {
+        let fields: Vec<&str> = line.split(',').collect();
+        (fields[0].parse().unwrap(), fields[1].parse().unwrap(), fields[2].parse().unwrap(), fields[3].parse().unwrap())
+    }).collect()
+}
+
+#[test]
+fn histograms_are_written_next_to_the_output_file()
+{
+    let output_file   = std::env::temp_dir().join("histograms_rows=8,cols=8.txt").to_string_lossy().into_owned();
+    let temperatures  = [1.5, 5.0];
+    let measure_steps = 400;
+    assert!(Simulation::builder().lattice(8, 8).temperatures(&temperatures).sweeps(10, 10).histograms(10, 0).build().is_err());
+
+    let simulation = Simulation::builder()
+        .lattice(8, 8)
+        .temperatures(&temperatures)
+        .sweeps(50, measure_steps)
+        .seed(11)
+        .output_file(&output_file)
+        .histograms(8, 16)
+        .build()
+        .unwrap();
+    let results = simulation.run();
+    // no scalar results
+    assert!(results[0].names().all(|name| !name.contains("histogram")));
+
+    for &temp in &temperatures
+    {
+        let magnetisation = Histograms::file_name_for(&output_file, temp, "magnetisation");
+        let energy        = Histograms::file_name_for(&output_file, temp, "energy");
+        let (p_m, p_e)    = (read_histogram(&magnetisation), read_histogram(&energy));
+        std::fs::remove_file(&magnetisation).unwrap();
+        std::fs::remove_file(&energy).unwrap();
+
+        assert_eq!((p_m.len(), p_e.len()), (8, 16));
+        assert_eq!(p_m.iter().map(|row| row.2).sum::<u64>(), measure_steps as u64);
+        assert_eq!(p_e.iter().map(|row| row.2).sum::<u64>(), measure_steps as u64);
+        assert_eq!((p_m[0].0, p_m[7].1), (-1_f64, 1_f64));
+        let integral: f64 = p_m.iter().map(|&(low, high, _, density)| density * (high - low)).sum();
+        assert!((integral - 1_f64).abs() < 1E-12);
+
+        let centre = p_m[3].2 + p_m[4].2;
+        let tails  = p_m[0].2 + p_m[7].2;
+        match temp < 2.269
+        {
+            // ordered phase: P(m) peaks at ±1, both signs visited by the cluster flips
+            true  => assert!(tails > centre && p_m[0].2 > 0 && p_m[7].2 > 0, "T={temp}: {p_m:?}"),
+            false => assert!(centre > tails, "T={temp}: {p_m:?}"),
+        }
+    }
+}
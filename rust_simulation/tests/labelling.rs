@@ -0,0 +1,147 @@
+// Cross-checks of the cluster labelling backends against each other & against the single pass Hoshen-Kopelman labelling
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+use swendsen_wang::swendsen_wang_algorithm::{
+    BondConfiguration, ClusterLabelling, FloodFill, IsingArray2D, LabelEquivalence, LabellingStrategy,
+    SwendsenWangAlgorithm, UnionFindLabelling, UnionFindStrategy, J,
+};
+
+const SHAPES: [(usize, usize); 4] = [(2, 2), (3, 5), (16, 16), (17, 40)];
+const TEMPERATURES: [f64; 3]      = [1.5, 2.269, 4.0];
+
+fn proba_add(temp: f64) -> f64
+{
+    1f64 - (-2_f64*J/temp).exp()
+}
+
+// renumbers the clusters in order of first appearance
+fn canonical(clusters: &[usize]) -> Vec<usize>
+{
+    let mut renumbered = vec![usize::MAX; clusters.len()];
+    let mut next       = 0;
+    clusters.iter().map(|&cluster|
+    {
+        if renumbered[cluster] == usize::MAX
+        {
+            renumbered[cluster] = next;
+            next += 1;
+        }
+        renumbered[cluster]
+    }).collect()
+}
+
+#[test]
+fn backends_agree_on_random_bond_configurations()
+{
+    let mut rng = SmallRng::seed_from_u64(42);
+    for (rows, cols) in SHAPES
+    {
+        let num_sites = rows*cols;
+        let mut backends: Vec<Box<dyn ClusterLabelling<usize>>> = vec![
+            Box::new(UnionFindLabelling::new(num_sites, UnionFindStrategy::SmallestLabel)),
+            Box::new(UnionFindLabelling::new(num_sites, UnionFindStrategy::BySize)),
+            Box::new(FloodFill::new(num_sites)),
+            Box::new(LabelEquivalence::new(num_sites)),
+        ];
+        let mut bonds = BondConfiguration::new(rows, cols);
+        for temp in TEMPERATURES
+        {
+            for _ in 0..20
+            {
+                let spins = IsingArray2D::new_randomized(&mut rng, rows, cols);
+                bonds.activate(&spins, &mut rng, proba_add(temp));
+
+                let results: Vec<(usize, Vec<usize>)> = backends.iter_mut().map(|backend|
+                {
+                    let mut clusters = vec![0; num_sites];
+                    let num_clusters = backend.label_clusters(&bonds, &mut clusters);
+                    (num_clusters, clusters)
+                }).collect();
+
+                let (num_clusters, clusters) = &results[0];
+                assert_eq!(canonical(clusters), *clusters, "clusters are not numbered in order of first appearance");
+                assert_eq!(clusters.iter().max().map(|&c| c + 1), Some(*num_clusters));
+                for other in &results[1..]
+                {
+                    assert_eq!(&results[0], other, "labelling backends disagree on a {rows}x{cols} lattice at T={temp}");
+                }
+                // every activated bond joins sites of the same cluster
+                for site in 0..num_sites
+                {
+                    for ngbr in bonds.bonded_neighbours(site)
+                    {
+                        assert_eq!(clusters[site], clusters[ngbr]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn fully_bonded_and_empty_configurations()
+{
+    let (rows, cols) = (6, 7);
+    let mut bonds    = BondConfiguration::new(rows, cols);
+    let mut clusters = vec![0; rows*cols];
+
+    assert_eq!(ClusterLabelling::<usize>::label_clusters(&mut FloodFill::new(rows*cols), &bonds, &mut clusters), rows*cols);
+    assert_eq!(clusters, (0..rows*cols).collect::<Vec<_>>());
+
+    // a single periodic row of bonds closes on itself
+    (0..cols).for_each(|x| bonds.set_right(2*cols + x, true));
+    assert_eq!(ClusterLabelling::<usize>::label_clusters(&mut LabelEquivalence::new(rows*cols), &bonds, &mut clusters), rows*cols - cols + 1);
+    assert!(clusters[2*cols..3*cols].iter().all(|&c| c == clusters[2*cols]));
+
+    (0..rows*cols).for_each(|site| { bonds.set_right(site, true); bonds.set_down(site, true); });
+    let mut union_find = UnionFindLabelling::<usize>::new(rows*cols, UnionFindStrategy::BySize);
+    assert_eq!(union_find.label_clusters(&bonds, &mut clusters), 1);
+    assert!(clusters.iter().all(|&c| c == 0));
+}
+
+// Same seed: the bonds are drawn in the same order, so every strategy must give the same clusters & the same chain
+#[test]
+fn strategies_reproduce_single_pass_hoshen_kopelman()
+{
+    let strategies = [LabellingStrategy::UnionFind, LabellingStrategy::FloodFill, LabellingStrategy::LabelEquivalence];
+    for (rows, cols) in SHAPES
+    {
+        for temp in TEMPERATURES
+        {
+            for union_find in [UnionFindStrategy::SmallestLabel, UnionFindStrategy::BySize]
+            {
+                let mut reference_rng   = SmallRng::seed_from_u64(7);
+                let mut reference_spins = IsingArray2D::new_randomized(&mut reference_rng, rows, cols);
+                let mut reference       = SwendsenWangAlgorithm::<usize>::with_union_find(rows, cols, union_find);
+
+                let mut others: Vec<_> = strategies.iter().map(|&labelling|
+                {
+                    let mut rng = SmallRng::seed_from_u64(7);
+                    let spins   = IsingArray2D::new_randomized(&mut rng, rows, cols);
+                    (rng, spins, SwendsenWangAlgorithm::<usize>::with_labelling(rows, cols, labelling, union_find))
+                }).collect();
+
+                for _ in 0..10
+                {
+                    let reference_result   = reference.perform_swendsen_wang_all(&reference_spins, &mut reference_rng, proba_add(temp));
+                    let reference_clusters = canonical(&(0..rows*cols).map(|site| reference.cluster_of(((site / cols) as i32, (site % cols) as i32))).collect::<Vec<_>>());
+                    reference.flip_cluster_and_take_fourier(&mut reference_spins, &mut reference_rng);
+                    reference.reset();
+
+                    for (rng, spins, swendsen_wang) in others.iter_mut()
+                    {
+                        let result   = swendsen_wang.perform_swendsen_wang_all(spins, rng, proba_add(temp));
+                        let clusters = (0..rows*cols).map(|site| swendsen_wang.cluster_of(((site / cols) as i32, (site % cols) as i32))).collect::<Vec<_>>();
+                        assert_eq!(result, reference_result);
+                        assert_eq!(clusters, reference_clusters, "{} disagrees with Hoshen-Kopelman on a {rows}x{cols} lattice at T={temp}", swendsen_wang.labelling().name());
+
+                        swendsen_wang.flip_cluster_and_take_fourier(spins, rng);
+                        swendsen_wang.reset();
+                        assert_eq!(spins.as_slice(), reference_spins.as_slice());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Writing results files in every format, reading them back & merging them
+use std::time::Duration;
+use swendsen_wang::monte_carlo_results::{MonteCarloResults, OutputFormat, ResultsFile, MEASUREMENTS_COLUMN};
+use swendsen_wang::simulation::{PrecisionTarget, Simulation};
+use swendsen_wang::snapshot::Snapshots;
+
+fn sample_results() -> (Vec<f64>, Vec<MonteCarloResults<f64>>)
+{
+    let temperatures = vec![0.5, 2.269, 3.25];
+    let results      = temperatures.iter().map(|&temp: &f64|
+    {
+        let mut res = MonteCarloResults::new();
+        res.push("energy_density", -2_f64 / temp);
+        res.push("magnetisation", (1_f64 / 3_f64).powf(temp));
+        res.push("correlation length", 1E-12 * temp);
+        res
+    }).collect();
+    (temperatures, results)
+}
+
+#[test]
+fn every_format_round_trips()
+{
+    let (temperatures, results) = sample_results();
+    let run_info     = [("rows", "16".to_string()), ("cols", "8".to_string()), ("seed", "42".to_string()), ("rng", "small".to_string())];
+    let elapsed_time = Duration::from_millis(1500);
+    let directory    = std::env::temp_dir();
+
+    for format in [OutputFormat::Legacy, OutputFormat::Csv, OutputFormat::Json, OutputFormat::Npy, OutputFormat::Npz]
+    {
+        let file_name = format.file_name_for(&directory.join(format!("round_trip_{}.txt", format.name())).to_string_lossy());
+        MonteCarloResults::write_as(format, &file_name, &temperatures, &results, elapsed_time, &run_info).unwrap();
+        let read = MonteCarloResults::<f64>::read_from_file(&file_name).unwrap();
+        if format == OutputFormat::Npy
+        {
+            // the run parameters are in the metadata file, without it only the results are left
+            std::fs::remove_file(OutputFormat::metadata_file_name_for(&file_name)).unwrap();
+            let results_only = MonteCarloResults::<f64>::read_from_file(&file_name).unwrap();
+            assert!(results_only.metadata.is_empty() && results_only.results == results);
+        }
+        std::fs::remove_file(&file_name).unwrap();
+
+        assert_eq!(read.format, format);
+        assert_eq!(read.temperatures, temperatures);
+        assert_eq!(read.results, results);
+        assert_eq!(read.metadata("seed"), Some("42"));
+        assert_eq!(read.metadata("rng"), Some("small"));
+        assert_eq!(read.shape(), Some((16, 8)));
+        let expected_time = if format == OutputFormat::Legacy { 1_f64 } else { 1.5 };
+        assert_eq!(read.elapsed_time(), Some(expected_time));
+    }
+}
+
+#[test]
+fn reads_the_stored_legacy_results()
+{
+    let file_name = concat!(env!("CARGO_MANIFEST_DIR"), "/results/overview/out_rows=8,cols=8.txt");
+    let read      = MonteCarloResults::<f64>::read_from_file(file_name).unwrap();
+
+    assert_eq!(read.format, OutputFormat::Legacy);
+    assert_eq!(read.elapsed_time(), Some(46_f64));
+    assert_eq!(read.shape(), Some((8, 8)));
+    assert_eq!(read.temperatures.len(), read.results.len());
+    assert_eq!(read.temperatures[0], 0.5);
+    let names: Vec<&str> = read.results[0].names().collect();
+    assert_eq!(names, ["energy_density", "magnetisation", "specific_heat", "susceptibility", "correlation length"]);
+    assert!(read.column("magnetisation").unwrap()[0] > 0.99);
+}
+
+#[test]
+fn rejects_malformed_files()
+{
+    let file_name = std::env::temp_dir().join("malformed_results.txt");
+    std::fs::write(&file_name, "temp, energy_density, elapsed_time: 3\n2.0, -1.5\n2.5\n").unwrap();
+    let read = MonteCarloResults::<f64>::read_from_file(&file_name.to_string_lossy());
+    std::fs::remove_file(&file_name).unwrap();
+
+    assert_eq!(read.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+// Results as Thermodynamics computes them from the total energies & magnetisations of every sweep
+fn thermodynamics(temp: f64, num_spins: f64, energies: &[f64], spin_sums: &[f64]) -> MonteCarloResults<f64>
+{
+    let mean    = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let squares = |values: &[f64]| values.iter().map(|v| v * v).collect::<Vec<_>>();
+
+    let mut res = MonteCarloResults::new();
+    res.push("energy_density", mean(energies) / num_spins);
+    res.push("magnetisation", mean(spin_sums) / num_spins);
+    res.push("specific_heat", (mean(&squares(energies)) - mean(energies).powi(2)) / (temp * temp * num_spins));
+    res.push("susceptibility", (mean(&squares(spin_sums)) - mean(spin_sums).powi(2)) / (temp * num_spins));
+    res
+}
+
+fn run(file_name: &str, measure_steps: usize, temperatures: Vec<f64>, results: Vec<MonteCarloResults<f64>>) -> ResultsFile<f64>
+{
+    let metadata = [("elapsed_time", "10"), ("rows", "4"), ("cols", "4"), ("measure_steps", &measure_steps.to_string()), ("seed", file_name)];
+    let metadata = metadata.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    ResultsFile { file_name: file_name.to_string(), format: OutputFormat::Legacy, temperatures, results, metadata }
+}
+
+#[test]
+fn merged_runs_match_the_pooled_samples()
+{
+    let num_spins = 16_f64;
+    let energies  = [-30_f64, -28., -32., -20., -24., -26., -32., -18.];
+    let spin_sums = [14_f64, 12., 16., 6., 10., 8., 16., 4.];
+    let (split, temp) = (3, 2.5);
+
+    let run_a  = run("1", split, vec![2.0, temp], vec![thermodynamics(2.0, num_spins, &energies, &spin_sums), thermodynamics(temp, num_spins, &energies[..split], &spin_sums[..split])]);
+    let run_b  = run("2", energies.len() - split, vec![temp, 3.0], vec![thermodynamics(temp, num_spins, &energies[split..], &spin_sums[split..]), thermodynamics(3.0, num_spins, &energies, &spin_sums)]);
+    let merged = ResultsFile::merge(&[run_a, run_b]).unwrap();
+
+    assert_eq!(merged.temperatures, [2.0, temp, 3.0]);
+    assert_eq!(merged.column(MEASUREMENTS_COLUMN).unwrap(), [split as f64, energies.len() as f64, (energies.len() - split) as f64]);
+    assert_eq!(merged.metadata("seed"), Some("1;2"));
+    assert_eq!(merged.metadata("merged_runs"), Some("2"));
+    assert_eq!(merged.elapsed_time(), Some(20_f64));
+
+    let pooled = thermodynamics(temp, num_spins, &energies, &spin_sums);
+    for (name, value) in pooled.iter()
+    {
+        let merged_value = merged.results[1].get(name).unwrap();
+        assert!((merged_value - value).abs() < 1E-12 * value.abs().max(1_f64), "{name}: {merged_value} != {value}");
+    }
+    // merged files can be merged again, their weights being the measurements column
+    let again = ResultsFile::merge(&[merged.clone(), merged]).unwrap();
+    assert_eq!(again.metadata("merged_runs"), Some("4"));
+    assert_eq!(again.column(MEASUREMENTS_COLUMN).unwrap(), [2. * split as f64, 2. * energies.len() as f64, 2. * (energies.len() - split) as f64]);
+}
+
+#[test]
+fn merging_checks_the_runs_are_compatible()
+{
+    let results = || vec![thermodynamics(2.0, 16., &[-30., -28.], &[14., 12.])];
+    let run_a   = run("1", 2, vec![2.0], results());
+
+    let mut other_size = run("2", 2, vec![2.0], results());
+    other_size.metadata[1].1 = "8".to_string();
+    assert!(ResultsFile::merge(&[run_a.clone(), other_size]).is_err());
+
+    let mut other_results = run("3", 2, vec![2.0], results());
+    other_results.results[0].push("correlation length", 1.);
+    assert!(ResultsFile::merge(&[run_a.clone(), other_results]).is_err());
+
+    assert!(ResultsFile::<f64>::merge(&[]).is_err());
+    assert!(ResultsFile::merge(&[run_a]).is_ok());
+}
+
+#[test]
+fn legacy_metadata_is_readable_or_rejected()
+{
+    let (temperatures, results) = sample_results();
+    let file_name = std::env::temp_dir().join("separator_results.txt").to_string_lossy().into_owned();
+    for metadata in [("file", "out_rows=8,cols=8.txt"), ("key: value", "1")]
+    {
+        let metadata = [(metadata.0.to_string(), metadata.1.to_string())];
+        let written  = MonteCarloResults::write_with_metadata(OutputFormat::Legacy, &file_name, &temperatures, &results, &metadata);
+        assert_eq!(written.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        MonteCarloResults::write_with_metadata(OutputFormat::Csv, &file_name, &temperatures, &results, &metadata).unwrap();
+    }
+
+    // every run parameter, with the options that describe lists, reads back as written
+    let simulation = Simulation::builder()
+        .lattice(6, 8)
+        .temperatures(&[1.5, 2.5])
+        .sweeps(10, 10)
+        .target_precision(PrecisionTarget { targets: PrecisionTarget::parse_targets("specific_heat=0.01, susceptibility=0.02").unwrap(), max_measure_steps: 100, time_limit: None })
+        .snapshots(Snapshots { temperatures: vec![1.5, 2.5], ..Snapshots::default() })
+        .output_file(&file_name)
+        .build()
+        .unwrap();
+    let run_info = simulation.run_parameters();
+    let empty    = vec![MonteCarloResults::new(); 2];
+    MonteCarloResults::write_as(OutputFormat::Legacy, &file_name, &[1.5, 2.5], &empty, Duration::ZERO, &run_info).unwrap();
+    let read = MonteCarloResults::<f64>::read_from_file(&file_name).unwrap();
+    std::fs::remove_file(&file_name).unwrap();
+    for (key, value) in &run_info
+    {
+        assert_eq!(read.metadata(key), Some(value.as_str()), "{key}");
+    }
+}
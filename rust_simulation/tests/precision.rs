@@ -0,0 +1,96 @@
+// Binning errors & runs stopped at a target precision
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use std::time::Duration;
+use swendsen_wang::monte_carlo_results::MEASUREMENTS_COLUMN;
+use swendsen_wang::simulation::{PrecisionTarget, Simulation};
+use swendsen_wang::statistics::Binning;
+
+// AR(1) series x_n = rho x_(n-1) + noise: variance 1, integrated autocorrelation time (1 + rho) / (2 (1 - rho))
+fn correlated_series(rho: f64, length: usize, seed: u64) -> Vec<f64>
+{
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let noise   = (3_f64 * (1_f64 - rho * rho)).sqrt(); // uniform [-1, 1] has variance 1/3
+    let mut x   = 0_f64;
+    (0..length).map(|_| { x = rho * x + noise * rng.random_range(-1_f64..1_f64); x }).collect()
+}
+
+#[test]
+fn binning_errors_account_for_the_autocorrelation()
+{
+    let length = 200_000;
+    for (rho, seed) in [(0_f64, 1), (0.9, 2)]
+    {
+        let series      = correlated_series(rho, length, seed);
+        let mut binning = Binning::<2>::default();
+        series.iter().for_each(|&x| binning.push([x, x * x]));
+        assert!(binning.num_bins() < Binning::<2>::MAX_BINS && binning.num_bins() >= Binning::<2>::MAX_BINS / 2);
+        assert_eq!(binning.num_bins() * binning.bin_size() + length % binning.bin_size(), length);
+
+        let tau      = (1_f64 + rho) / (2_f64 * (1_f64 - rho));
+        let expected = (2_f64 * tau / length as f64).sqrt();
+        let error    = binning.jackknife_error(|means| means[0]);
+        assert!((error / expected - 1_f64).abs() < 0.3, "rho={rho}: {error} != {expected}");
+        // the mean is its own jackknife estimator: same error as the spread of the bin averages
+        let variance = binning.jackknife_error(|means| means[1] - means[0].powi(2));
+        assert!(variance > 0_f64 && variance < 10_f64 * expected);
+
+        let mut restored = Binning::<2>::default();
+        assert_eq!(restored.load(&binning.to_vec()), Some(binning.to_vec().len()));
+        assert_eq!(restored, binning);
+    }
+    assert!(Binning::<1>::default().jackknife_error(|means| means[0]).is_nan());
+}
+
+#[test]
+fn runs_sweep_until_the_target_is_reached()
+{
+    let temperatures = [2.269, 4.0];
+    let (measure_steps, max_measure_steps) = (500, 50_000);
+    let target     = 0.02;
+    let precision  = PrecisionTarget { targets: vec![("specific_heat".to_string(), target)], max_measure_steps, time_limit: None };
+    let simulation = |precision: PrecisionTarget| Simulation::builder()
+        .lattice(16, 16)
+        .temperatures(&temperatures)
+        .sweeps(100, measure_steps)
+        .seed(9)
+        .target_precision(precision)
+        .build()
+        .unwrap();
+
+    let results = simulation(precision.clone()).run();
+    for (&temp, res) in temperatures.iter().zip(&results)
+    {
+        let measurements = res.get(MEASUREMENTS_COLUMN).unwrap() as usize;
+        assert!(measurements >= measure_steps && measurements < max_measure_steps, "T={temp}: {measurements}");
+        assert_eq!(measurements % PrecisionTarget::CHECK_INTERVAL, 0);
+        let relative_error = res.get("specific_heat_err").unwrap() / res.get("specific_heat").unwrap();
+        assert!(relative_error <= target, "T={temp}: {relative_error}");
+        assert!(res.get("energy_density_err").unwrap() > 0_f64);
+    }
+    // the specific heat fluctuates most (relatively) near Tc: more sweeps are spent there
+    assert!(results[0].get(MEASUREMENTS_COLUMN).unwrap() > results[1].get(MEASUREMENTS_COLUMN).unwrap());
+
+    // budgets: nothing beyond measure_steps once the time is up
+    let no_time = simulation(PrecisionTarget { time_limit: Some(Duration::ZERO), ..precision.clone() }).run();
+    assert!(no_time.iter().all(|res| res.get(MEASUREMENTS_COLUMN) == Some(measure_steps as f64)));
+    let tight   = simulation(PrecisionTarget { targets: vec![("specific_heat".to_string(), 1E-6)], max_measure_steps: 1000, time_limit: None }).run();
+    assert!(tight.iter().all(|res| res.get(MEASUREMENTS_COLUMN) == Some(1000_f64)));
+}
+
+#[test]
+fn precision_targets_are_checked()
+{
+    let valid  = || Simulation::builder().lattice(8, 8).temperatures(&[2.0]).sweeps(10, 100);
+    let target = |targets: &str, max_measure_steps: usize| PrecisionTarget { targets: PrecisionTarget::parse_targets(targets).unwrap(), max_measure_steps, time_limit: None };
+
+    assert_eq!(PrecisionTarget::parse_targets("specific_heat=0.005, susceptibility = 0.01").unwrap(), [("specific_heat".to_string(), 0.005), ("susceptibility".to_string(), 0.01)]);
+    assert!(PrecisionTarget::parse_targets("specific_heat: 0.005").is_err());
+    assert!(PrecisionTarget::parse_targets("specific_heat=small").is_err());
+    assert_eq!(target("magnetisation=0.01;energy_density=0.001", 100).targets_description(), "magnetisation=0.01;energy_density=0.001");
+
+    assert!(valid().target_precision(target("specific_heat=0.01", 1000)).build().is_ok());
+    assert!(valid().target_precision(target("", 1000)).build().is_err());
+    assert!(valid().target_precision(target("correlation length=0.01", 1000)).build().is_err());
+    assert!(valid().target_precision(target("specific_heat=0", 1000)).build().is_err());
+    assert!(valid().target_precision(target("specific_heat=0.01", 50)).build().is_err());
+}
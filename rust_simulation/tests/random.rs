@@ -0,0 +1,55 @@
+// Random generators
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rand_pcg::Pcg64;
+use swendsen_wang::random::{Mt64, RngState, Xoshiro256PlusPlus};
+
+#[test]
+fn mersenne_twister_reproduces_the_reference_output()
+{
+    // mt19937-64.c with init_genrand64(5489), the default seed of std::mt19937_64
+    let mut rng = Mt64::seed_from_u64(5489);
+    let first: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+    assert_eq!(first, [14514284786278117030, 4620546740167642908, 13109570281517897720]);
+
+    // the C++ standard's check: the 10000th output, many state regenerations later
+    let mut rng = Mt64::seed_from_u64(5489);
+    assert_eq!((0..10_000).map(|_| rng.next_u64()).last(), Some(9981545732273789042));
+}
+
+#[test]
+fn small_generator_reproduces_small_rng()
+{
+    for seed in [0, 42, u64::MAX]
+    {
+        let mut small     = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut reference = rand::rngs::SmallRng::seed_from_u64(seed);
+        assert!((0..1000).all(|_| small.next_u64() == reference.next_u64()), "seed {seed}");
+        assert_eq!(small.next_u32(), reference.next_u32());
+    }
+}
+
+fn assert_state_round_trips<R: RngCore + SeedableRng + RngState>(name: &str)
+{
+    let mut rng = R::seed_from_u64(7);
+    for draws in [0, 1, 3, 1000]
+    {
+        (0..draws).for_each(|_| { rng.next_u32(); });
+        let mut restored = R::from_state(&rng.state()).unwrap();
+        let expected: Vec<u64> = (0..700).map(|_| rng.next_u64()).collect();
+        assert!(expected.iter().all(|&value| restored.next_u64() == value), "{name} after {draws} draws");
+    }
+    assert!(R::from_state(&[1, 2, 3]).is_none(), "{name}");
+}
+
+#[test]
+fn generator_states_round_trip()
+{
+    assert_state_round_trips::<Xoshiro256PlusPlus>("small");
+    assert_state_round_trips::<ChaCha20Rng>("chacha");
+    assert_state_round_trips::<Mt64>("mt64");
+    assert_state_round_trips::<Pcg64>("pcg");
+    // the states of the different generators cannot be mistaken for one another
+    assert!(Mt64::from_state(&Xoshiro256PlusPlus::seed_from_u64(1).state()).is_none());
+    assert!(ChaCha20Rng::from_state(&Pcg64::seed_from_u64(1).state()).is_none());
+}
@@ -0,0 +1,206 @@
+// Driving runs through the library
+use swendsen_wang::observables::{Measurement, Observable, Summary};
+use swendsen_wang::simulation::{Sampler, Simulation};
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+use swendsen_wang::swendsen_wang_algorithm::{
+    BitPackedIsingArray2D, ClusterUpdate, IsingArray2D, LabellingStrategy, LatticeBackend, ParallelSwendsenWang, SpinLattice,
+    SwendsenWangAlgorithm, UnionFindStrategy,
+};
+
+#[test]
+fn builder_rejects_invalid_runs()
+{
+    let valid = || Simulation::builder().lattice(8, 8).temperatures(&[2.0]).sweeps(10, 10);
+    assert!(valid().build().is_ok());
+    assert!(valid().lattice(1, 8).build().is_err());
+    assert!(valid().temperatures(&[]).build().is_err());
+    assert!(valid().temperatures(&[2.0, -1.0]).build().is_err());
+    assert!(valid().sweeps(10, 0).build().is_err());
+    assert!(valid().checkpoint_interval(5).build().is_err());
+    assert!(valid().checkpoint_interval(5).output_file("out.txt").build().is_ok());
+    assert!(valid().histograms(0, 10).build().is_err());
+    assert!(valid().sampler(Sampler { strips: 2, ..Sampler::default() }).build().is_ok());
+    assert!(valid().sampler(Sampler { strips: 2, labelling: LabellingStrategy::FloodFill, ..Sampler::default() }).build().is_err());
+    assert!(valid().sampler(Sampler { strips: 2, union_find: UnionFindStrategy::BySize, ..Sampler::default() }).build().is_err());
+    assert!(valid().sampler(Sampler { strips: 1, union_find: UnionFindStrategy::BySize, ..Sampler::default() }).build().is_ok());
+}
+
+#[test]
+fn runs_are_reproducible_from_the_seed()
+{
+    let temperatures = [1.5, 2.269, 3.5];
+    let simulation   = |sampler: Sampler| Simulation::builder()
+        .lattice(12, 10)
+        .temperatures(&temperatures)
+        .sweeps(50, 200)
+        .measure_structure_factor(true)
+        .seed(1234)
+        .sampler(sampler)
+        .build()
+        .unwrap();
+
+    let reference = simulation(Sampler::default());
+    assert_eq!(reference.seed(), 1234);
+    let results = reference.run();
+    assert_eq!(results.len(), temperatures.len());
+
+    let again   = reference.run();
+    let flood   = simulation(Sampler { labelling: LabellingStrategy::FloodFill, ..Default::default() }).run();
+    let compact = simulation(Sampler { lattice_backend: LatticeBackend::Compact, ..Default::default() }).run();
+    for others in [again, flood, compact]
+    {
+        for (result, other) in results.iter().zip(&others)
+        {
+            assert_eq!(result, other);
+        }
+    }
+    // ordered phase at low temperature, disordered at high temperature
+    assert!(results[0].get("magnetisation").unwrap() > 0.9);
+    assert!(results[2].get("magnetisation").unwrap() < 0.5);
+    assert!(results[1].get("correlation length").unwrap() > 0_f64);
+}
+
+// mean number of clusters & fraction of the lattice in the largest cluster
+#[derive(Default)]
+struct ClusterStatistics
+{
+    num_clusters: f64,
+    largest: f64,
+    num_measurements: usize,
+}
+
+impl Observable for ClusterStatistics
+{
+    fn measure(&mut self, measurement: &Measurement)
+    {
+        let clusters = measurement.clusters.expect("clusters were requested");
+        let largest  = clusters.largest_cluster().map(|label| clusters.size_of(label)).unwrap_or(0);
+        self.num_clusters     += clusters.num_clusters() as f64;
+        self.largest          += largest as f64;
+        self.num_measurements += 1;
+    }
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        assert_eq!(self.num_measurements, summary.num_measurements);
+        let num_measurements = summary.num_measurements as f64;
+        vec![
+            ("num_clusters".to_string(), self.num_clusters / num_measurements),
+            ("largest_cluster".to_string(), self.largest / (num_measurements * summary.num_spins())),
+        ]
+    }
+    fn needs_clusters(&self) -> bool
+    {
+        true
+    }
+    fn save_state(&self) -> Vec<f64>
+    {
+        vec![self.num_clusters, self.largest, self.num_measurements as f64]
+    }
+    fn load_state(&mut self, state: &[f64]) -> Option<usize>
+    {
+        let &[num_clusters, largest, num_measurements, ..] = state else
+        {
+            return None;
+        };
+        *self = Self { num_clusters, largest, num_measurements: num_measurements as usize };
+        Some(3)
+    }
+}
+
+// no accumulated state: the summary has all it needs, nothing to checkpoint
+struct MeasurementCount;
+
+impl Observable for MeasurementCount
+{
+    fn measure(&mut self, _measurement: &Measurement) {}
+    fn results(&self, summary: &Summary) -> Vec<(String, f64)>
+    {
+        vec![("measurement_count".to_string(), summary.num_measurements as f64)]
+    }
+}
+
+#[test]
+fn custom_observables_are_appended_after_the_default_ones()
+{
+    let run = |strips: usize| Simulation::builder()
+        .lattice(16, 16)
+        .temperatures(&[1.5, 5.0])
+        .sweeps(20, 100)
+        .seed(99)
+        .sampler(Sampler { strips, ..Default::default() })
+        .observable(ClusterStatistics::default)
+        .observable(|| MeasurementCount)
+        .build()
+        .unwrap()
+        .run();
+
+    for results in [run(1), run(4)]
+    {
+        let names: Vec<&str> = results[0].names().collect();
+        assert_eq!(names, ["energy_density", "magnetisation", "specific_heat", "susceptibility", "correlation length",
+                           "signed_magnetisation", "susceptibility_m2", "sign_flip_rate", "num_clusters", "largest_cluster", "measurement_count"]);
+        assert_eq!(results[0].get("measurement_count"), Some(100_f64));
+        // structure factor not requested
+        assert_eq!(results[0].get("correlation length"), Some(0_f64));
+
+        let (cold, hot) = (&results[0], &results[1]);
+        assert!(cold.get("largest_cluster").unwrap() > 0.5);
+        assert!(hot.get("largest_cluster").unwrap() < 0.2);
+        assert!(cold.get("num_clusters").unwrap() < hot.get("num_clusters").unwrap());
+    }
+}
+
+#[test]
+fn susceptibility_estimators_are_consistent()
+{
+    let (rows, cols) = (12, 12);
+    let temperatures = [1.8, 2.269, 4.0];
+    let results = Simulation::builder()
+        .lattice(rows, cols)
+        .temperatures(&temperatures)
+        .sweeps(50, 1000)
+        .seed(5)
+        .build()
+        .unwrap()
+        .run();
+
+    let num_spins = (rows * cols) as f64;
+    for (&temp, res) in temperatures.iter().zip(&results)
+    {
+        let value = |name: &str| res.get(name).unwrap();
+        // chi - chi' = N <|m|>² / T
+        let difference = value("susceptibility_m2") - value("susceptibility");
+        let expected   = num_spins * value("magnetisation").powi(2) / temp;
+        assert!((difference - expected).abs() < 1E-9 * expected, "T={temp}: {difference} != {expected}");
+
+        assert!(value("signed_magnetisation").abs() <= value("magnetisation"));
+        assert!((0_f64..=1_f64).contains(&value("sign_flip_rate")));
+    }
+    // the largest cluster is flipped every other sweep on average: the sign is not frozen even in the ordered phase
+    assert!(results[0].get("sign_flip_rate").unwrap() > 0.3);
+}
+
+// every cluster update returns the signed spin sum of the configuration, which the signed magnetisation is built on
+fn assert_signed_spin_sums<S: SpinLattice, U: ClusterUpdate<S>>(mut swendsen_wang: U, name: &str)
+{
+    let (rows, cols) = (6, 10);
+    let mut rng      = SmallRng::seed_from_u64(3);
+    let randomized   = IsingArray2D::new_randomized(&mut rng, rows, cols);
+    for spins in [IsingArray2D::from_spins(rows, cols, vec![-1; rows * cols]).unwrap(), IsingArray2D::from_spins(rows, cols, vec![1; rows * cols]).unwrap(), randomized]
+    {
+        let expected      = spins.as_slice().iter().map(|&spin| spin as f64).sum::<f64>();
+        let (_, spin_sum) = swendsen_wang.perform_swendsen_wang_all(&S::from_ising_array(spins), &mut rng, 0.5);
+        swendsen_wang.reset();
+        assert_eq!(spin_sum, expected, "{name}");
+    }
+}
+
+#[test]
+fn cluster_updates_return_signed_spin_sums()
+{
+    assert_signed_spin_sums::<IsingArray2D, _>(SwendsenWangAlgorithm::<usize>::new(6, 10), "standard");
+    assert_signed_spin_sums::<BitPackedIsingArray2D, _>(SwendsenWangAlgorithm::<u32>::new(6, 10), "compact");
+    assert_signed_spin_sums::<IsingArray2D, _>(SwendsenWangAlgorithm::<usize>::with_labelling(6, 10, LabellingStrategy::FloodFill, UnionFindStrategy::default()), "flood fill");
+    assert_signed_spin_sums::<IsingArray2D, _>(ParallelSwendsenWang::<usize>::new(6, 10, 2), "strips");
+}
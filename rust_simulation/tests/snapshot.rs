@@ -0,0 +1,81 @@
+// Spin & cluster images
+use swendsen_wang::configuration::InitialState;
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::snapshot::{cluster_colour, Image, ImageFormat, Snapshots};
+use swendsen_wang::swendsen_wang_algorithm::ClusterMap;
+
+#[test]
+fn spin_images_as_netpbm()
+{
+    let spins = InitialState::Striped.configuration(2, 3).unwrap().unwrap();
+    let image = Image::from_spins(&spins, 2);
+    assert_eq!((image.width(), image.height(), image.channels()), (6, 4, 1));
+    assert_eq!(image.extension(ImageFormat::Netpbm), "pgm");
+
+    let mut bytes = Vec::new();
+    image.write(&mut bytes, ImageFormat::Netpbm).unwrap();
+    let header = b"P5\n6 4\n255\n";
+    assert_eq!(&bytes[..header.len()], header);
+    assert_eq!(&bytes[header.len()..], [[255_u8; 12], [0_u8; 12]].concat());
+}
+
+#[test]
+fn cluster_images_as_png()
+{
+    // clusters 0 | 1 1 / 2 | 1 1
+    let clusters = ClusterMap::from_cluster_indices(2, 3, [4, 2, 2, 5, 2, 2]);
+    let image    = Image::from_clusters(&clusters, 1);
+    assert_eq!((image.width(), image.height(), image.channels()), (3, 2, 3));
+    assert_eq!(image.extension(ImageFormat::Netpbm), "ppm");
+    let pixel = |site: usize| &image.pixels()[3 * site..3 * site + 3];
+    assert_eq!(pixel(0), cluster_colour(0));
+    assert!([1, 2, 4, 5].into_iter().all(|site| pixel(site) == cluster_colour(1)));
+    assert_eq!(pixel(3), cluster_colour(2));
+    assert!((0..16).all(|label| cluster_colour(label) != cluster_colour(label + 1)));
+
+    let mut bytes = Vec::new();
+    image.write(&mut bytes, ImageFormat::Png).unwrap();
+    let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info       = reader.next_frame(&mut pixels).unwrap();
+    assert_eq!((info.width, info.height, info.color_type), (3, 2, png::ColorType::Rgb));
+    assert_eq!(&pixels[..info.buffer_size()], image.pixels());
+}
+
+#[test]
+fn snapshots_during_the_measurements()
+{
+    let output_file  = std::env::temp_dir().join("snapshot_rows=8,cols=10.txt").to_string_lossy().into_owned();
+    let temperatures = [1.5, 3.5];
+    let snapshots    = Snapshots { interval: 5, temperatures: vec![3.5], clusters: true, format: ImageFormat::Png, scale: 3 };
+    let simulation   = |snapshots: Option<Snapshots>|
+    {
+        let builder = Simulation::builder().lattice(8, 10).temperatures(&temperatures).sweeps(10, 12).seed(9).output_file(&output_file);
+        match snapshots
+        {
+            Some(snapshots) => builder.snapshots(snapshots),
+            None            => builder,
+        }.build().unwrap().run()
+    };
+
+    // the images use no random numbers
+    assert_eq!(simulation(Some(snapshots.clone())), simulation(None));
+    for sweeps in [15, 20]
+    {
+        for image in ["spins", "clusters"]
+        {
+            let file_name = Snapshots::file_name_for(&output_file, 3.5, sweeps, image, "png");
+            let info      = png::Decoder::new(std::fs::File::open(&file_name).unwrap()).read_info().unwrap().info().clone();
+            assert_eq!((info.width, info.height), (30, 24));
+            std::fs::remove_file(&file_name).unwrap();
+        }
+    }
+    assert!(!std::path::Path::new(&Snapshots::file_name_for(&output_file, 3.5, 10, "spins", "png")).exists());
+    assert!(!std::path::Path::new(&Snapshots::file_name_for(&output_file, 1.5, 15, "spins", "png")).exists());
+
+    let valid = || Simulation::builder().lattice(8, 10).temperatures(&temperatures).sweeps(10, 12).output_file(&output_file);
+    assert!(valid().snapshots(Snapshots { interval: 0, ..snapshots.clone() }).build().is_err());
+    assert!(valid().snapshots(Snapshots { temperatures: vec![2.0], ..snapshots.clone() }).build().is_err());
+    assert!(Simulation::builder().lattice(8, 10).temperatures(&temperatures).sweeps(10, 12).snapshots(snapshots).build().is_err());
+    assert_eq!("PGM".parse(), Ok(ImageFormat::Netpbm));
+}
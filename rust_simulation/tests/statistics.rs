@@ -0,0 +1,66 @@
+// Streaming moments against two-pass references
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::statistics::RunningMoments;
+use swendsen_wang::time_series::{TimeSeriesFormat, TimeSeriesWriter};
+
+fn two_pass_variance(values: &[f64]) -> f64
+{
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+#[test]
+fn small_fluctuations_on_a_large_mean()
+{
+    // total energies of a 1024² lattice: ~ -2·10⁶, fluctuating by a few hundreds
+    let mut rng = SmallRng::seed_from_u64(3);
+    let values: Vec<f64> = (0..500_000).map(|_| -2_000_000_f64 + 4_f64 * rng.random_range(-100..=100) as f64).collect();
+
+    let mut moments = RunningMoments::default();
+    values.iter().for_each(|&v| moments.push(v));
+
+    let reference = two_pass_variance(&values);
+    assert_eq!(moments.count(), values.len());
+    assert!((moments.mean() - values.iter().sum::<f64>() / values.len() as f64).abs() < 1E-6);
+    assert!((moments.variance() - reference).abs() < 1E-9 * reference, "{} != {reference}", moments.variance());
+
+    let restored = RunningMoments::from_array(moments.to_array());
+    assert_eq!(restored, moments);
+    assert_eq!(RunningMoments::default().variance(), 0_f64);
+}
+
+#[test]
+fn specific_heat_and_susceptibility_match_the_time_series()
+{
+    let (rows, cols, temp) = (16, 16, 2.269);
+    let output_file = std::env::temp_dir().join("statistics_fluctuations.txt").to_string_lossy().into_owned();
+    let results = Simulation::builder()
+        .lattice(rows, cols)
+        .temperatures(&[temp])
+        .sweeps(50, 2000)
+        .seed(11)
+        .output_file(&output_file)
+        .time_series(TimeSeriesFormat::Binary)
+        .build()
+        .unwrap()
+        .run();
+
+    let series_file = TimeSeriesWriter::file_name_for(&output_file, temp, TimeSeriesFormat::Binary);
+    let bytes       = std::fs::read(&series_file).unwrap();
+    std::fs::remove_file(&series_file).unwrap();
+    let records: Vec<[f64; 5]> = bytes.chunks_exact(40)
+        .map(|record| std::array::from_fn(|k| f64::from_le_bytes(record[8*k..8*k + 8].try_into().unwrap())))
+        .collect();
+    assert_eq!(records.len(), 2000);
+
+    let num_spins = (rows * cols) as f64;
+    let energies: Vec<f64>  = records.iter().map(|record| record[0]).collect();
+    let spin_sums: Vec<f64> = records.iter().map(|record| record[1].abs()).collect();
+    let specific_heat  = two_pass_variance(&energies) / (temp * temp * num_spins);
+    let susceptibility = two_pass_variance(&spin_sums) / (temp * num_spins);
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1E-10 * b.abs();
+    assert!(close(results[0].get("specific_heat").unwrap(), specific_heat));
+    assert!(close(results[0].get("susceptibility").unwrap(), susceptibility));
+}
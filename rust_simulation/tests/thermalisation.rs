@@ -0,0 +1,91 @@
+// Automatic thermalisation from a hot & a cold start
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use swendsen_wang::checkpoint::Checkpoint;
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::thermalisation::{EquilibrationTest, Thermalisation};
+
+#[test]
+fn equilibration_needs_agreeing_energies()
+{
+    let mut rng = SmallRng::seed_from_u64(8);
+    let mut agreeing = EquilibrationTest::default();
+    let mut apart    = EquilibrationTest::default();
+    for _ in 0..EquilibrationTest::MIN_SWEEPS
+    {
+        agreeing.push(-100_f64 + rng.random_range(-5_f64..5_f64), -100_f64 + rng.random_range(-5_f64..5_f64));
+        apart.push(-80_f64 + rng.random_range(-5_f64..5_f64), -120_f64 + rng.random_range(-5_f64..5_f64));
+    }
+    assert!(agreeing.is_equilibrated());
+    assert!(!apart.is_equilibrated());
+
+    // only checked every CHECK_INTERVAL sweeps
+    agreeing.push(-100_f64, -100_f64);
+    assert!(!agreeing.is_equilibrated());
+    // frozen replicas: equal energies agree, different ones never do
+    let mut frozen = EquilibrationTest::default();
+    (0..EquilibrationTest::MIN_SWEEPS).for_each(|_| frozen.push(-128_f64, -128_f64));
+    assert!(frozen.is_equilibrated());
+    assert!("auto".parse::<Thermalisation>().is_ok() && "often".parse::<Thermalisation>().is_err());
+}
+
+#[test]
+fn automatic_thermalisation_stops_early()
+{
+    let temperatures = [1.5, 2.269, 4.0];
+    let max_sweeps   = 2000;
+    let simulation   = |thermalisation: Thermalisation, therm_steps: usize| Simulation::builder()
+        .lattice(16, 16)
+        .temperatures(&temperatures)
+        .sweeps(therm_steps, 200)
+        .thermalisation(thermalisation)
+        .seed(21)
+        .build()
+        .unwrap()
+        .run();
+
+    let auto = simulation(Thermalisation::Auto, max_sweeps);
+    for (&temp, res) in temperatures.iter().zip(&auto)
+    {
+        let sweeps = res.get("thermalisation_sweeps").unwrap() as usize;
+        assert!((EquilibrationTest::MIN_SWEEPS..max_sweeps).contains(&sweeps), "T={temp}: {sweeps} sweeps");
+
+        // the hot replica goes through the same sweeps as with a fixed thermalisation of that length
+        let fixed = simulation(Thermalisation::Fixed, sweeps);
+        let index = temperatures.iter().position(|&t| t == temp).unwrap();
+        assert!(fixed[index].iter().all(|(name, value)| res.get(name) == Some(value)), "T={temp}");
+    }
+    assert!(simulation(Thermalisation::Fixed, 10)[0].get("thermalisation_sweeps").is_none());
+    assert!(simulation(Thermalisation::Auto, 5)[0].get("thermalisation_sweeps") == Some(5_f64));
+}
+
+#[test]
+fn resumed_runs_keep_their_thermalisation()
+{
+    let output_file = std::env::temp_dir().join("thermalisation_rows=10,cols=10.txt").to_string_lossy().into_owned();
+    let temperature = 2.5;
+    let simulation  = |measure_steps: usize, resume: bool| Simulation::builder()
+        .lattice(10, 10)
+        .temperatures(&[temperature])
+        .sweeps(1000, measure_steps)
+        .thermalisation(Thermalisation::Auto)
+        .seed(3)
+        .output_file(&output_file)
+        .checkpoint_interval(1)
+        .resume(resume)
+        .build()
+        .unwrap();
+
+    let reference = simulation(300, false).run();
+    let checkpoint_file = Checkpoint::file_name_for(&output_file, temperature);
+    let checkpoint      = Checkpoint::read_from_file(&checkpoint_file).unwrap();
+    let sweeps          = reference[0].get("thermalisation_sweeps").unwrap() as usize;
+    assert_eq!(checkpoint.therm_steps, sweeps);
+    assert_eq!(checkpoint.sweeps_done, sweeps + 300);
+
+    // a shorter run leaves its checkpoint behind, the longer one continues from it without thermalising again
+    simulation(100, false).run();
+    let resumed = simulation(300, true);
+    let results = resumed.run();
+    resumed.remove_checkpoints();
+    assert_eq!(results, reference);
+}
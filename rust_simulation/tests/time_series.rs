@@ -0,0 +1,73 @@
+// Per-sweep time series written by the simulation & read back
+use swendsen_wang::simulation::Simulation;
+use swendsen_wang::time_series::{TimeSeriesFormat, TimeSeriesWriter, TIME_SERIES_COLUMNS};
+
+fn read_time_series(file_name: &str, format: TimeSeriesFormat) -> Vec<Vec<f64>>
+{
+    match format
+    {
+        TimeSeriesFormat::Csv =>
+        {
+            let text      = std::fs::read_to_string(file_name).unwrap();
+            let mut lines = text.lines();
+            assert_eq!(lines.next(), Some(TIME_SERIES_COLUMNS.join(", ").as_str()));
+            lines.map(|line| line.split(',').map(|value| value.trim().parse().unwrap()).collect()).collect()
+        }
+        TimeSeriesFormat::Binary =>
+        {
+            let bytes = std::fs::read(file_name).unwrap();
+            let values: Vec<f64> = bytes.chunks_exact(8).map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap())).collect();
+            values.chunks(TIME_SERIES_COLUMNS.len()).map(<[f64]>::to_vec).collect()
+        }
+    }
+}
+
+#[test]
+fn time_series_hold_every_measurement()
+{
+    let (rows, cols)  = (6, 10);
+    let num_spins     = (rows * cols) as f64;
+    let temperatures  = [2.0, 3.0];
+    let output_file   = std::env::temp_dir().join("time_series_rows=6,cols=10.txt").to_string_lossy().into_owned();
+    let close         = |a: f64, b: f64| (a - b).abs() <= 1e-9 * (1_f64 + b.abs());
+
+    for format in [TimeSeriesFormat::Csv, TimeSeriesFormat::Binary]
+    {
+        let results = Simulation::builder()
+            .lattice(rows, cols)
+            .temperatures(&temperatures)
+            .sweeps(20, 200)
+            .measure_structure_factor(true)
+            .seed(11)
+            .output_file(&output_file)
+            .time_series(format)
+            .build()
+            .unwrap()
+            .run();
+
+        for (&temp, result) in temperatures.iter().zip(&results)
+        {
+            let file_name = TimeSeriesWriter::file_name_for(&output_file, temp, format);
+            let records   = read_time_series(&file_name, format);
+            std::fs::remove_file(&file_name).unwrap();
+            assert_eq!(records.len(), 200, "{format:?} at T={temp}");
+            assert!(records.iter().all(|record| record.len() == TIME_SERIES_COLUMNS.len()));
+
+            // the records are the measurements the results were accumulated from
+            let mean = |value: &dyn Fn(&Vec<f64>) -> f64| records.iter().map(value).sum::<f64>() / (records.len() as f64 * num_spins);
+            assert!(close(mean(&|record| record[0]), result.get("energy_density").unwrap()), "{format:?} at T={temp}");
+            assert!(close(mean(&|record| record[1].abs()), result.get("magnetisation").unwrap()), "{format:?} at T={temp}");
+            assert!(close(mean(&|record| record[1]), result.get("signed_magnetisation").unwrap()), "{format:?} at T={temp}");
+            // spin_sum is taken before the flip of a sweep, re_spin_q0 = spin_sum / sqrt(N) after it
+            for pair in records.windows(2)
+            {
+                assert!(close(pair[1][1], pair[0][2] * num_spins.sqrt()), "{format:?} at T={temp}");
+            }
+            // signed: the magnetisation changes sign in the disordered phase
+            if temp > 2.269
+            {
+                assert!(records.iter().any(|record| record[1] > 0_f64) && records.iter().any(|record| record[1] < 0_f64));
+            }
+        }
+    }
+}
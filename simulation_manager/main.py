@@ -19,17 +19,26 @@ class IsingData(ExperimentOutput):
         self.specific_heat      = []
         self.mag_susceptibility = []
         self.elapsed_time       = -1
+        self.seed               = None
         self.correlation_length = []
+        self.optional_columns   = {name: [] for name in ["signed_magnetisation", "susceptibility_m2", "sign_flip_rate", "thermalisation_sweeps", "sweeps_per_measurement", "measurements",
+                                                     "energy_density_err", "magnetisation_err", "specific_heat_err", "susceptibility_err"]} # newer files only
 
     @override
     def parse_output(self, line_number, line):
         if line_number == 0:
-            slines = line.split(':')
-            try:
-                self.observables  = slines[0].split(', ')
-                self.elapsed_time = float(slines[1])
-            except Exception as _:
-                self.observables = line.split(',')
+            # "temp, energy_density, ..., elapsed_time: 46, seed: 1234" (older files have no seed)
+            fields           = [field.strip() for field in line.split(',')]
+            self.observables = [field for field in fields if ':' not in field]
+            for field in fields:
+                if ':' not in field:
+                    continue
+                key, value = (part.strip() for part in field.split(':', 1))
+                if key == "elapsed_time":
+                    self.elapsed_time = float(value)
+                elif key == "seed":
+                    self.seed = int(value)
+            if self.elapsed_time < 0:
                 print("No elasped time found.")
         else:
             slines = line.split(", ")
@@ -39,6 +48,9 @@ class IsingData(ExperimentOutput):
             self.specific_heat.append(float(slines[3]))
             self.mag_susceptibility.append(float(slines[4]))
             self.correlation_length.append(float(slines[5]))
+            for name, values in self.optional_columns.items():
+                if name in self.observables:
+                    values.append(float(slines[self.observables.index(name)]))
     
 class RustIsingExperimentCreator:
     def __init__(self, folder: str, name: str):
@@ -46,11 +58,15 @@ class RustIsingExperimentCreator:
         self.builder.set_scale_variable_names(["rows","cols"])
         self.builder.set_output_type(IsingData)
 
-    def new_from_parameters(self, therm_steps: dict, measure_steps: dict, temperatures: np.ndarray, measure_struct_fact: bool = False) -> RustExperiment:
+    def new_from_parameters(self, therm_steps: dict, measure_steps: dict, temperatures: np.ndarray, measure_struct_fact: bool = False, time_series: str = "none", thermalisation: str = "fixed", initial_state: str = "random", anneal: bool = False) -> RustExperiment:
         
         self.builder.set_cargo_toml_path(CARGO_TOML_PATH)
         self.builder.add_static_parameter("temperatures", temperatures)
         self.builder.add_static_parameter("measure_struct_fact", measure_struct_fact)
+        self.builder.add_static_parameter("time_series", time_series) # "none", "csv" or "binary"
+        self.builder.add_static_parameter("thermalisation", thermalisation) # "auto": therm_steps is only an upper bound
+        self.builder.add_static_parameter("initial_state", initial_state) # "random", "up", "down", "striped" or a configuration file
+        self.builder.add_static_parameter("anneal", anneal) # each temperature starts from the last configuration of the previous one
         self.builder.add_scaling_parameter("therm_steps", therm_steps)
         self.builder.add_scaling_parameter("measure_steps", measure_steps)
         return self.builder.build()